@@ -0,0 +1,95 @@
+use crate::tuning::Tuning;
+
+pub struct BatteryTuning {
+    pub max: f32,
+    pub thrust_cost: f32,
+    pub ping_cost: f32,
+    pub ping_range: f32,
+    pub low: f32,
+    pub low_range: f32,
+    pub beacon_cost: f32,
+    pub recharge: f32,
+}
+
+impl BatteryTuning {
+    pub fn new(tuning: &Tuning) -> Self {
+        Self {
+            max: tuning.get("battery.max"),
+            thrust_cost: tuning.get("battery.thrust_cost"),
+            ping_cost: tuning.get("battery.ping_cost"),
+            ping_range: tuning.get("battery.ping_range"),
+            low: tuning.get("battery.low"),
+            low_range: tuning.get("battery.low_range"),
+            beacon_cost: tuning.get("battery.beacon_cost"),
+            recharge: tuning.get("battery.recharge"),
+        }
+    }
+}
+
+// Power shared by the motor, the sonar and the beacon launcher.
+pub struct Battery {
+    pub charge: f32,
+    pub tuning: BatteryTuning,
+}
+
+impl Battery {
+    pub fn new(tuning: BatteryTuning) -> Self {
+        Self {
+            charge: tuning.max,
+            tuning,
+        }
+    }
+
+    pub fn update(&mut self, thrust: f32, dt: f32) {
+        self.charge += (self.tuning.recharge - self.tuning.thrust_cost * thrust.abs()) * dt;
+        self.charge = self.charge.clamp(0., self.tuning.max);
+    }
+
+    // How much of the requested thrust the motor can deliver, the sub drifts when drained.
+    pub fn thrust_power(&self) -> f32 {
+        if self.charge > 0. { 1. } else { 0. }
+    }
+
+    // Range of the next ping, or 0 if there isn't enough charge for one.
+    pub fn sonar_range(&self) -> f32 {
+        let range = if self.fraction() < self.tuning.low {
+            self.tuning.low_range
+        } else {
+            self.tuning.ping_range
+        };
+
+        if self.charge >= range * self.tuning.ping_cost {
+            range
+        } else {
+            0.
+        }
+    }
+
+    pub fn ping(&mut self, range: f32) {
+        self.drain(range * self.tuning.ping_cost);
+    }
+
+    pub fn can_place_beacon(&self) -> bool {
+        self.charge >= self.tuning.beacon_cost
+    }
+
+    pub fn place_beacon(&mut self) {
+        self.drain(self.tuning.beacon_cost);
+    }
+
+    pub fn add(&mut self, amount: f32) {
+        self.charge = (self.charge + amount).min(self.tuning.max);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        // A battery tuned to hold nothing is always flat.
+        if self.tuning.max <= 0. {
+            return 0.;
+        }
+        (self.charge / self.tuning.max).clamp(0., 1.)
+    }
+
+    fn drain(&mut self, amount: f32) {
+        self.charge = (self.charge - amount).max(0.);
+    }
+}
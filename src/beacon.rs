@@ -2,24 +2,24 @@ use notan::{app::Color, math::Vec2};
 
 use crate::{echo::Echo, send_echo};
 
+// Emitter component of beacon entities, pings on its own every `freq` frames.
+#[derive(Clone, Debug, PartialEq)]
 pub struct Beacon {
-    pub pos: Vec2,
     pub visible: bool,
-    freq: i32,
-    timer: f32,
+    pub freq: i32,
+    pub timer: f32,
 }
 
 impl Beacon {
-    pub fn new(x: f32, y: f32, visible: bool, freq: i32) -> Self {
+    pub fn new(visible: bool, freq: i32) -> Self {
         Self {
-            pos: Vec2::new(x, y),
             visible,
             freq,
             timer: 0.,
         }
     }
 
-    pub fn update(&mut self, echoes: &mut Vec<Echo>, dt: f32) {
+    pub fn update(&mut self, pos: &Vec2, echoes: &mut Vec<Echo>, dt: f32) -> bool {
         self.timer += dt * 60.;
 
         if self.timer > self.freq as f32 {
@@ -31,7 +31,19 @@ impl Beacon {
                 Color::TRANSPARENT
             };
 
-            send_echo(echoes, &self.pos, fastrand::f32(), color);
+            send_echo(
+                echoes,
+                pos,
+                fastrand::f32(),
+                color,
+                false,
+                f32::INFINITY,
+                61,
+            );
+
+            return true;
         }
+
+        false
     }
 }
@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const APP_DIR: &str = "blind_depths";
+
+// Simple `key = value` files stored in the platform config directory.
+// On the web build there is no filesystem, so loading yields nothing and saving is a no-op.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn config_dir() -> Option<PathBuf> {
+    use std::env::var_os;
+
+    let base = if cfg!(target_os = "windows") {
+        var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        var_os("HOME").map(|h| PathBuf::from(h).join("Library/Application Support"))
+    } else {
+        var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+    };
+
+    base.map(|b| b.join(APP_DIR))
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn config_dir() -> Option<PathBuf> {
+    None
+}
+
+pub fn parse(text: &str) -> HashMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .filter_map(|l| l.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+pub fn serialize(values: &[(String, String)]) -> String {
+    let mut text = String::new();
+    for (key, value) in values {
+        text.push_str(&format!("{key} = {value}\n"));
+    }
+    text
+}
+
+pub fn load(name: &str) -> HashMap<String, String> {
+    read(name).map(|text| parse(&text)).unwrap_or_default()
+}
+
+pub fn save(name: &str, values: &[(String, String)]) {
+    write(name, &serialize(values));
+}
+
+pub fn read(name: &str) -> Option<String> {
+    config_dir().and_then(|dir| std::fs::read_to_string(dir.join(name)).ok())
+}
+
+pub fn write(name: &str, text: &str) {
+    let Some(dir) = config_dir() else {
+        return;
+    };
+
+    if let Err(e) = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(dir.join(name), text))
+    {
+        notan::log::warn!("Could not save {name}: {e}");
+    }
+}
@@ -3,8 +3,9 @@ use notan::{
     math::Vec2,
 };
 
-use crate::{RENDER_HEIGHT, RENDER_WIDTH, camera::Camera2D, get_bg_color};
+use crate::{RENDER_HEIGHT, RENDER_WIDTH, camera::Camera2D, sdf::DistanceField, terrain::Terrain};
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Echo {
     pub pos: Vec2,
     pub dir: f32,
@@ -12,6 +13,9 @@ pub struct Echo {
     pub hit_color: Color,
     pub no_find: bool,
     pub lifetime: f32,
+    pub audible: bool,
+    // Distance left before the echo fades out without hitting anything.
+    pub range: f32,
 }
 
 impl Echo {
@@ -23,21 +27,53 @@ impl Echo {
             hit_color: color,
             no_find: false,
             lifetime: 1.,
+            audible: false,
+            range: f32::INFINITY,
         }
     }
 
-    pub fn update(&mut self, bytes: &Vec<u8>, app: &mut App, camera: &Camera2D) {
+    pub fn update(
+        &mut self,
+        terrain: &Terrain,
+        field: &DistanceField,
+        app: &mut App,
+        camera: &Camera2D,
+    ) {
         if self.lifetime <= 0. {
             return;
         }
 
         if !self.hit && !self.no_find {
-            self.pos += Vec2::from_angle(self.dir) * 6. * app.timer.delta_f32() * 60.;
+            let scale = Vec2::new(
+                camera.work_size.x / (RENDER_WIDTH / 0.5),
+                camera.work_size.y / (RENDER_HEIGHT / 0.5),
+            );
+            let dir = Vec2::from_angle(self.dir);
 
-            let x = self.pos.x * (camera.work_size.x / (RENDER_WIDTH / 0.5));
-            let y = self.pos.y * (camera.work_size.y / (RENDER_HEIGHT / 0.5));
+            // Sphere trace the step: no wall is closer than the field's distance, so the
+            // echo can jump that far at once. It still moves at least a pixel per jump,
+            // so only walls thinner than that can be skipped.
+            let step = 6. * app.timer.delta_f32() * 60.;
+            let mut left = step;
+            loop {
+                let clear = field.distance(self.pos * scale);
+                if clear <= 0. || left <= 0. {
+                    break;
+                }
+                let advance = clear.max(1.).min(left);
+                self.pos += dir * advance;
+                left -= advance;
+            }
+
+            self.range -= step - left;
+            if self.range <= 0. {
+                self.no_find = true;
+                self.hit_color = Color::TRANSPARENT;
+                return;
+            }
 
-            let color = get_bg_color(bytes, x, y);
+            let pos = self.pos * scale;
+            let color = terrain.color(pos.x, pos.y);
             if color != Color::BLACK {
                 if color == Color::RED {
                     self.no_find = true;
@@ -0,0 +1,748 @@
+use notan::{
+    app::{Color, Graphics, Texture},
+    draw::{Draw, DrawImages, DrawShapes, DrawTransform},
+    math::Vec2,
+};
+
+use crate::{
+    beacon::Beacon,
+    echo::Echo,
+    events::{Event, EventBus},
+    hazard::Hazard,
+    material::Material,
+    sdf::DistanceField,
+    terrain::Terrain,
+};
+
+// Everything living in the cave besides the player is an entity in one arena. An entity
+// is a bag of optional components, the systems below only touch the ones they need.
+pub type EntityId = usize;
+
+// How close to a wall a diver swims before steering away from it.
+const WALL_CLEARANCE: f32 = 24.;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Kind {
+    Survivor,
+    Creature,
+    Beacon,
+    Log,
+    Current,
+    Vent,
+    Rockfall,
+    Torpedo,
+}
+
+impl Kind {
+    pub fn key(&self) -> &'static str {
+        match self {
+            Kind::Survivor => "survivor",
+            Kind::Creature => "creature",
+            Kind::Beacon => "beacon",
+            Kind::Log => "log",
+            Kind::Current => "current",
+            Kind::Vent => "vent",
+            Kind::Rockfall => "rockfall",
+            Kind::Torpedo => "torpedo",
+        }
+    }
+}
+
+// `pos` is the top-left corner of the sprite, like the player's.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Transform {
+    pub pos: Vec2,
+    // Radians.
+    pub dir: f32,
+}
+
+// How close the player has to come to touch the entity.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Collider {
+    pub radius: f32,
+}
+
+// What the entity looks like on sonar. Echoes passing within `radius` of its center
+// bounce back in `color`, each step with a `strength` chance, so 1 is as solid as rock.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SonarSignature {
+    pub radius: f32,
+    pub color: Color,
+    pub strength: f32,
+    // Whether a return makes the entity itself show up, rather than just the echo dots.
+    pub reveal: bool,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SurvivorCondition {
+    Calm,
+    // Swims slowly once found.
+    Injured,
+    // Only trusts a sub that comes in slowly, and breathes fast.
+    Panicking,
+    // Starts with little air left.
+    LowOxygen,
+}
+
+impl SurvivorCondition {
+    pub const ALL: [SurvivorCondition; 4] = [
+        SurvivorCondition::Calm,
+        SurvivorCondition::Injured,
+        SurvivorCondition::Panicking,
+        SurvivorCondition::LowOxygen,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            SurvivorCondition::Calm => "calm",
+            SurvivorCondition::Injured => "injured",
+            SurvivorCondition::Panicking => "panicking",
+            SurvivorCondition::LowOxygen => "low_oxygen",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<SurvivorCondition> {
+        SurvivorCondition::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    pub fn follow_speed(self) -> f32 {
+        match self {
+            SurvivorCondition::Injured => 1.5,
+            _ => 3.,
+        }
+    }
+
+    // Multiplies the tuned starting air and drain of a found diver.
+    pub fn air_start(self) -> f32 {
+        match self {
+            SurvivorCondition::LowOxygen => 0.4,
+            _ => 1.,
+        }
+    }
+
+    pub fn air_drain(self) -> f32 {
+        match self {
+            SurvivorCondition::Panicking => 1.5,
+            _ => 1.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Ai {
+    // Waits to be reached, then follows the player home. `air` is filled in once found.
+    Survivor {
+        found: bool,
+        condition: SurvivorCondition,
+        air: f32,
+    },
+    // Sleeps until something wakes it, then charges. Loud noises draw it towards `lure`
+    // in its sleep.
+    Creature {
+        awake: bool,
+        speed: f32,
+        lure: Option<Vec2>,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Sprite {
+    Diver,
+    Monster,
+    Beacon,
+    Log,
+    Torpedo,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Renderable {
+    pub sprite: Sprite,
+    pub size: Vec2,
+    pub visible: bool,
+}
+
+// Goes off when it touches anything solid, or once `fuel` frames have passed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Warhead {
+    pub radius: f32,
+    pub fuel: f32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entity {
+    pub kind: Kind,
+    pub transform: Transform,
+    pub velocity: Vec2,
+    pub collider: Option<Collider>,
+    pub signature: Option<SonarSignature>,
+    pub ai: Option<Ai>,
+    pub render: Option<Renderable>,
+    pub emitter: Option<Beacon>,
+    // Index of the story log picked up by touching the entity.
+    pub lore: Option<usize>,
+    pub hazard: Option<Hazard>,
+    pub warhead: Option<Warhead>,
+    // Cleared by `despawn`, the entity is dropped from the arena on the next `sweep`.
+    pub alive: bool,
+}
+
+impl Entity {
+    fn new(kind: Kind, pos: Vec2) -> Self {
+        Self {
+            kind,
+            transform: Transform { pos, dir: 0. },
+            velocity: Vec2::ZERO,
+            collider: None,
+            signature: None,
+            ai: None,
+            render: None,
+            emitter: None,
+            lore: None,
+            hazard: None,
+            warhead: None,
+            alive: true,
+        }
+    }
+
+    pub fn survivor(pos: Vec2, condition: SurvivorCondition) -> Self {
+        Self {
+            collider: Some(Collider { radius: 80. }),
+            signature: Some(SonarSignature {
+                radius: 16.,
+                color: Color::WHITE,
+                strength: 1.,
+                reveal: true,
+            }),
+            ai: Some(Ai::Survivor {
+                found: false,
+                condition,
+                air: 0.,
+            }),
+            render: Some(Renderable {
+                sprite: Sprite::Diver,
+                size: Vec2::splat(32.),
+                visible: false,
+            }),
+            ..Self::new(Kind::Survivor, pos)
+        }
+    }
+
+    pub fn creature(pos: Vec2) -> Self {
+        Self {
+            ai: Some(Ai::Creature {
+                awake: false,
+                speed: 15.,
+                lure: None,
+            }),
+            // Only the outline shows up while it sleeps.
+            signature: Some(SonarSignature {
+                radius: 100.,
+                color: Color::from_rgba(0.7, 0.1, 0.15, 1.),
+                strength: 0.5,
+                reveal: false,
+            }),
+            render: Some(Renderable {
+                sprite: Sprite::Monster,
+                size: Vec2::splat(256.),
+                visible: false,
+            }),
+            ..Self::new(Kind::Creature, pos)
+        }
+    }
+
+    pub fn beacon(pos: Vec2, visible: bool, freq: i32) -> Self {
+        Self {
+            render: Some(Renderable {
+                sprite: Sprite::Beacon,
+                size: Vec2::splat(40.),
+                visible,
+            }),
+            emitter: Some(Beacon::new(visible, freq)),
+            ..Self::new(Kind::Beacon, pos)
+        }
+    }
+
+    pub fn log(pos: Vec2, index: usize) -> Self {
+        Self {
+            collider: Some(Collider { radius: 24. }),
+            signature: Some(SonarSignature {
+                radius: 14.,
+                color: Color::from_rgba(0.4, 0.8, 1., 1.),
+                strength: 1.,
+                reveal: true,
+            }),
+            render: Some(Renderable {
+                sprite: Sprite::Log,
+                size: Vec2::splat(10.),
+                visible: false,
+            }),
+            lore: Some(index),
+            ..Self::new(Kind::Log, pos)
+        }
+    }
+
+    // `pos` is where it leaves the sub, `dir` in radians.
+    pub fn torpedo(pos: Vec2, dir: f32, speed: f32, radius: f32) -> Self {
+        let size = Vec2::new(12., 4.);
+        let mut torpedo = Self {
+            velocity: Vec2::from_angle(dir) * speed,
+            render: Some(Renderable {
+                sprite: Sprite::Torpedo,
+                size,
+                visible: true,
+            }),
+            warhead: Some(Warhead { radius, fuel: 240. }),
+            ..Self::new(Kind::Torpedo, pos - size / 2.)
+        };
+        torpedo.transform.dir = dir;
+        torpedo
+    }
+
+    // Hazards have no sprite, their position is the center of the area they cover.
+    pub fn current(pos: Vec2, radius: f32, flow: Vec2) -> Self {
+        Self {
+            hazard: Some(Hazard::Current { radius, flow }),
+            ..Self::new(Kind::Current, pos)
+        }
+    }
+
+    pub fn vent(pos: Vec2, radius: f32) -> Self {
+        Self {
+            hazard: Some(Hazard::Vent { radius }),
+            // The shimmer of hot water sends back a faint, scattered return.
+            signature: Some(SonarSignature {
+                radius,
+                color: Color::from_rgba(1., 0.45, 0.1, 1.),
+                strength: 0.03,
+                reveal: false,
+            }),
+            ..Self::new(Kind::Vent, pos)
+        }
+    }
+
+    pub fn rockfall(pos: Vec2, radius: f32) -> Self {
+        Self {
+            hazard: Some(Hazard::Rockfall {
+                radius,
+                fallen: false,
+            }),
+            ..Self::new(Kind::Rockfall, pos)
+        }
+    }
+
+    pub fn pos(&self) -> Vec2 {
+        self.transform.pos
+    }
+
+    pub fn center(&self) -> Vec2 {
+        match &self.render {
+            Some(render) => self.transform.pos + render.size / 2.,
+            None => self.transform.pos,
+        }
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.render.is_some_and(|r| r.visible)
+    }
+
+    pub fn is_found(&self) -> bool {
+        matches!(self.ai, Some(Ai::Survivor { found: true, .. }))
+    }
+
+    pub fn is_awake(&self) -> bool {
+        matches!(self.ai, Some(Ai::Creature { awake: true, .. }))
+    }
+}
+
+pub struct Entities {
+    pub list: Vec<Entity>,
+    textures: Vec<Texture>,
+}
+
+impl Entities {
+    pub fn new(gfx: &mut Graphics, list: Vec<Entity>) -> Self {
+        let mut texture = |bytes: &[u8]| gfx.create_texture().from_image(bytes).build().unwrap();
+
+        Self {
+            list,
+            // Indexed by `Sprite`, beacons are drawn as shapes.
+            textures: vec![
+                texture(include_bytes!("assets/player.png")),
+                texture(include_bytes!("assets/monster.png")),
+            ],
+        }
+    }
+
+    pub fn spawn(&mut self, entity: Entity) -> EntityId {
+        self.list.push(entity);
+        self.list.len() - 1
+    }
+
+    // Strips the entity so no system sees it again. It keeps its slot until `sweep`,
+    // so ids handed out this frame stay valid.
+    pub fn despawn(&mut self, id: EntityId) {
+        if let Some(entity) = self.list.get_mut(id) {
+            let kind = entity.kind;
+            let pos = entity.transform.pos;
+            *entity = Entity {
+                alive: false,
+                ..Entity::new(kind, pos)
+            };
+        }
+    }
+
+    // Called at the start of a frame, before any ids are handed out.
+    pub fn sweep(&mut self) {
+        self.list.retain(|e| e.alive);
+    }
+
+    pub fn get(&self, id: EntityId) -> Option<&Entity> {
+        self.list.get(id)
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut Entity> {
+        self.list.get_mut(id)
+    }
+
+    pub fn of_kind(&self, kind: Kind) -> impl Iterator<Item = &Entity> {
+        self.list.iter().filter(move |e| e.alive && e.kind == kind)
+    }
+
+    pub fn any_found(&self) -> bool {
+        self.list.iter().any(Entity::is_found)
+    }
+
+    // Lowest tank among the divers following the sub.
+    pub fn lowest_air(&self) -> Option<f32> {
+        self.list
+            .iter()
+            .filter_map(|e| match e.ai {
+                Some(Ai::Survivor {
+                    found: true, air, ..
+                }) => Some(air),
+                _ => None,
+            })
+            .reduce(f32::min)
+    }
+
+    pub fn rescued(&self) -> Vec<SurvivorCondition> {
+        self.list
+            .iter()
+            .filter_map(|e| match e.ai {
+                Some(Ai::Survivor {
+                    found: true,
+                    condition,
+                    ..
+                }) => Some(condition),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Survivors reached so far, and how many there are in the level.
+    // Survivors are never despawned and keep their order through saves and restores,
+    // so their place among the others names them.
+    pub fn survivor_index(&self, id: EntityId) -> usize {
+        self.list
+            .iter()
+            .take(id)
+            .filter(|e| e.kind == Kind::Survivor)
+            .count()
+    }
+
+    pub fn rescues(&self) -> (usize, usize) {
+        let found = self
+            .of_kind(Kind::Survivor)
+            .filter(|s| s.is_found())
+            .count();
+        (found, self.of_kind(Kind::Survivor).count())
+    }
+
+    pub fn awake_creature(&self) -> Option<&Entity> {
+        self.list.iter().find(|e| e.is_awake())
+    }
+
+    pub fn nearest(&self, kind: Kind, pos: Vec2) -> Option<&Entity> {
+        self.of_kind(kind).min_by(|a, b| {
+            a.center()
+                .distance(pos)
+                .total_cmp(&b.center().distance(pos))
+        })
+    }
+
+    pub fn wake_creatures(&mut self, events: &mut EventBus) {
+        for entity in &mut self.list {
+            if let Some(Ai::Creature { awake, .. }) = &mut entity.ai
+                && !*awake
+            {
+                *awake = true;
+                if let Some(render) = &mut entity.render {
+                    render.visible = true;
+                }
+                events.emit(Event::MonsterAwakened {
+                    pos: entity.transform.pos,
+                });
+            }
+        }
+    }
+
+    // AI system: decides where everything wants to go this frame.
+    pub fn update_ai(
+        &mut self,
+        player_pos: Vec2,
+        player_speed: f32,
+        field: &DistanceField,
+        events: &mut EventBus,
+    ) {
+        for (id, entity) in self.list.iter_mut().enumerate() {
+            let pos = entity.transform.pos;
+            match &mut entity.ai {
+                Some(Ai::Survivor {
+                    found, condition, ..
+                }) => {
+                    let to_player = (player_pos - pos).normalize_or_zero();
+                    entity.transform.dir = to_player.to_angle();
+                    entity.velocity = Vec2::ZERO;
+
+                    if !*found {
+                        let reach = entity.collider.map_or(0., |c| c.radius);
+                        let calm = *condition != SurvivorCondition::Panicking || player_speed < 1.5;
+                        if pos.distance(player_pos) < reach && calm {
+                            *found = true;
+                            if let Some(render) = &mut entity.render {
+                                render.visible = true;
+                            }
+                            events.emit(Event::FriendFound { id, pos });
+                        }
+                    } else if pos.distance(player_pos) > 70. {
+                        entity.velocity = to_player * condition.follow_speed();
+                    }
+
+                    // Divers keep off the walls while they swim.
+                    let center = pos + entity.render.map_or(Vec2::ZERO, |r| r.size / 2.);
+                    let clearance = field.distance(center);
+                    if *found && clearance < WALL_CLEARANCE {
+                        let push = (WALL_CLEARANCE - clearance) / WALL_CLEARANCE;
+                        entity.velocity += field.normal(center) * push * condition.follow_speed();
+                    }
+                }
+                Some(Ai::Creature { awake, speed, lure }) => {
+                    let center =
+                        entity.transform.pos + entity.render.map_or(Vec2::ZERO, |r| r.size / 2.);
+
+                    // Lured all the way to the sub, it wakes up.
+                    if !*awake && lure.is_some() && center.distance(player_pos + 16.) < 120. {
+                        *awake = true;
+                        if let Some(render) = &mut entity.render {
+                            render.visible = true;
+                        }
+                        events.emit(Event::MonsterAwakened { pos });
+                    }
+
+                    entity.velocity = match *lure {
+                        _ if *awake => Vec2::new(-*speed, 0.),
+                        Some(target) if center.distance(target) > 32. => {
+                            (target - center).normalize_or_zero() * *speed * 0.1
+                        }
+                        _ => {
+                            *lure = None;
+                            Vec2::ZERO
+                        }
+                    };
+                }
+                None => {}
+            }
+        }
+    }
+
+    // Pickup system: logs are collected by touching them.
+    pub fn touch(&mut self, player_center: Vec2, events: &mut EventBus) {
+        for id in 0..self.list.len() {
+            let entity = &self.list[id];
+            let (Some(collider), Some(index)) = (entity.collider, entity.lore) else {
+                continue;
+            };
+
+            if entity.center().distance(player_center) < collider.radius {
+                self.despawn(id);
+                events.emit(Event::LogCollected { index });
+            }
+        }
+    }
+
+    // Hazard system: how fast the water at `pos` is moving.
+    pub fn flow_at(&self, pos: Vec2) -> Vec2 {
+        self.list
+            .iter()
+            .filter_map(|e| e.hazard.map(|h| h.flow(e.center(), pos)))
+            .sum()
+    }
+
+    pub fn is_hot(&self, pos: Vec2) -> bool {
+        self.list
+            .iter()
+            .any(|e| e.hazard.is_some_and(|h| h.is_hot(e.center(), pos)))
+    }
+
+    // Currents carry travelling echoes along and vents knock them off course.
+    pub fn disturb(&self, echo: &mut Echo, dt: f32) {
+        if echo.hit || echo.no_find {
+            return;
+        }
+
+        echo.pos += self.flow_at(echo.pos) * dt * 60.;
+        if self.is_hot(echo.pos) {
+            echo.dir += (fastrand::f32() - 0.5) * 0.4;
+        }
+    }
+
+    // A noise heard up to `loudness` away from `source` brings down loose rock nearby,
+    // and draws sleeping creatures towards it when it's at least `lure` loud.
+    pub fn hear(&mut self, source: Vec2, loudness: f32, lure: f32, events: &mut EventBus) {
+        for entity in &mut self.list {
+            let center = entity.center();
+            if let Some(Ai::Creature {
+                awake: false,
+                lure: target,
+                ..
+            }) = &mut entity.ai
+                && loudness >= lure
+                && source.distance(center) < loudness
+            {
+                *target = Some(source);
+            }
+
+            if let Some(hazard) = &mut entity.hazard
+                && hazard.shaken(center, source, loudness)
+                && let Hazard::Rockfall { radius, fallen } = hazard
+            {
+                *fallen = true;
+                events.emit(Event::Rockfall {
+                    pos: center,
+                    radius: *radius,
+                });
+            }
+        }
+    }
+
+    // Torpedo system: warheads go off against anything that isn't water.
+    pub fn detonate(&mut self, terrain: &Terrain, dt: f32, events: &mut EventBus) {
+        for id in 0..self.list.len() {
+            let entity = &mut self.list[id];
+            let center = entity.center();
+            let Some(warhead) = &mut entity.warhead else {
+                continue;
+            };
+
+            warhead.fuel -= dt * 60.;
+            if warhead.fuel <= 0. || terrain.material(center.x, center.y) != Material::Water {
+                let radius = warhead.radius;
+                self.despawn(id);
+                events.emit(Event::Blast {
+                    pos: center,
+                    radius,
+                });
+            }
+        }
+    }
+
+    // Movement system, velocities are in pixels per frame at 60 fps.
+    pub fn integrate(&mut self, dt: f32) {
+        for entity in &mut self.list {
+            entity.transform.pos += entity.velocity * dt * 60.;
+        }
+    }
+
+    // Emitter system, returns where beacons pinged so they can be heard.
+    pub fn update_emitters(&mut self, echoes: &mut Vec<Echo>, dt: f32) -> Vec<Vec2> {
+        let mut pinged = vec![];
+        for entity in &mut self.list {
+            let pos = entity.transform.pos;
+            if let Some(emitter) = &mut entity.emitter
+                && emitter.update(&pos, echoes, dt)
+            {
+                pinged.push(entity.center());
+            }
+        }
+        pinged
+    }
+
+    // Sonar system: a travelling echo that passes through a signature may bounce off it,
+    // coming back in the signature's color. Returns the entity it bounced off.
+    pub fn reflect(&mut self, echo: &mut Echo, events: &mut EventBus) -> Option<EntityId> {
+        if echo.hit || echo.no_find {
+            return None;
+        }
+
+        for (id, entity) in self.list.iter_mut().enumerate() {
+            let Some(signature) = entity.signature else {
+                continue;
+            };
+            if echo.pos.distance(entity.center()) >= signature.radius
+                || fastrand::f32() >= signature.strength
+            {
+                continue;
+            }
+
+            echo.hit = true;
+            echo.hit_color = signature.color;
+
+            if signature.reveal
+                && let Some(render) = &mut entity.render
+                && !render.visible
+            {
+                render.visible = true;
+                if entity.kind == Kind::Survivor {
+                    let pos = entity.transform.pos;
+                    events.emit(Event::FriendDetected { pos });
+                }
+            }
+
+            return Some(id);
+        }
+
+        None
+    }
+
+    // Render system.
+    pub fn render(&self, draw: &mut Draw) {
+        for entity in &self.list {
+            let Some(render) = entity.render.filter(|r| r.visible) else {
+                continue;
+            };
+            let pos = entity.transform.pos;
+
+            match render.sprite {
+                Sprite::Beacon => {
+                    let center = entity.center();
+                    draw.circle(10.)
+                        .position(center.x, center.y)
+                        .stroke_color(Color::from_rgba(0.8, 0.0, 0.8, 1.0))
+                        .stroke(4.);
+                }
+                Sprite::Log => {
+                    draw.rect((pos.x, pos.y), (render.size.x, render.size.y))
+                        .stroke_color(Color::from_rgba(0.4, 0.8, 1., 1.))
+                        .stroke(2.);
+                }
+                Sprite::Torpedo => {
+                    draw.rect((0., 0.), (render.size.x, render.size.y))
+                        .color(Color::from_rgba(1., 0.85, 0.3, 1.))
+                        .rotate_from(
+                            (render.size.x / 2., render.size.y / 2.),
+                            entity.transform.dir,
+                        )
+                        .translate(pos.x, pos.y);
+                }
+                sprite => {
+                    draw.image(&self.textures[sprite as usize])
+                        .rotate_from(
+                            (render.size.x / 2., render.size.y / 2.),
+                            entity.transform.dir,
+                        )
+                        .translate(pos.x, pos.y);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use notan::{app::Color, math::Vec2};
+
+use crate::{Scene, entity::EntityId};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DamageSource {
+    Impact,
+    Monster,
+    Vent,
+    Rockfall,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    // `entity` is set when the echo bounced off an entity instead of the cave.
+    EchoHit {
+        pos: Vec2,
+        color: Color,
+        audible: bool,
+        entity: Option<EntityId>,
+    },
+    FriendDetected {
+        pos: Vec2,
+    },
+    FriendFound {
+        id: EntityId,
+        pos: Vec2,
+    },
+    MonsterAwakened {
+        pos: Vec2,
+    },
+    PlayerDamaged {
+        amount: f32,
+        source: DamageSource,
+    },
+    // A salvage or rescue reward paid into the profile.
+    CreditsEarned {
+        amount: u32,
+        reason: &'static str,
+    },
+    // `index` is the log's place in the level's list of logs.
+    LogCollected {
+        index: usize,
+    },
+    BeaconPlaced {
+        pos: Vec2,
+    },
+    // Something loud enough to be heard up to `loudness` pixels away.
+    Noise {
+        pos: Vec2,
+        loudness: f32,
+    },
+    Rockfall {
+        pos: Vec2,
+        radius: f32,
+    },
+    // A torpedo went off, breaking what it can within `radius`.
+    Blast {
+        pos: Vec2,
+        radius: f32,
+    },
+    SceneChanged {
+        from: Scene,
+        to: Scene,
+    },
+}
+
+type Listener = Box<dyn FnMut(&Event)>;
+
+// Events are queued while the frame runs and handed out once at the end of `update`,
+// so the code raising them never has to know who is listening.
+pub struct EventBus {
+    queue: Vec<Event>,
+    listeners: Vec<Listener>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self {
+            queue: vec![],
+            listeners: vec![],
+        }
+    }
+
+    pub fn emit(&mut self, event: Event) {
+        self.queue.push(event);
+    }
+
+    // For listeners that only need the event itself, like telemetry or achievements.
+    pub fn subscribe(&mut self, listener: impl FnMut(&Event) + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    // Hands the queued events to the listeners and returns them for the game systems.
+    pub fn drain(&mut self) -> Vec<Event> {
+        let events = std::mem::take(&mut self.queue);
+        for event in &events {
+            for listener in &mut self.listeners {
+                listener(event);
+            }
+        }
+        events
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,187 @@
+use gilrs::{Axis, Button, EventType, Gamepad, Gilrs};
+use notan::log;
+
+use crate::{
+    input::{Action, Controls},
+    ui::UiInput,
+};
+
+// Stick travel ignored around the center, so a worn stick doesn't drift the sub.
+const DEAD_ZONE: f32 = 0.15;
+
+// Buttons that act like a bound key.
+const BUTTONS: [(Button, Action); 4] = [
+    (Button::South, Action::Ping),
+    (Button::West, Action::DropBeacon),
+    (Button::East, Action::Torpedo),
+    (Button::Start, Action::Pause),
+];
+
+// How far the stick has to lean before it moves menu focus.
+const STICK_STEP: f32 = 0.5;
+
+// Reads every connected gamepad into `Controls`: the left stick turns, the right
+// stick strafes, the right and left triggers thrust and reverse. In menus the D-pad
+// or left stick moves focus, South confirms and East goes back.
+pub struct Gamepads {
+    // None when the platform has no gamepad support, the keyboard still works.
+    gilrs: Option<Gilrs>,
+    // The D-pad direction the left stick was leaning last frame.
+    stick: Option<Button>,
+}
+
+impl Gamepads {
+    pub fn new() -> Self {
+        let gilrs = Gilrs::new()
+            .map_err(|e| log::warn!("Gamepads are unavailable: {e}"))
+            .ok();
+        Self { gilrs, stick: None }
+    }
+
+    // Call after `Controls::update`, which resets the values to the keyboard's.
+    pub fn update(&mut self, controls: &mut Controls, ui: &mut UiInput) {
+        let Some(gilrs) = &mut self.gilrs else {
+            return;
+        };
+
+        while let Some(event) = gilrs.next_event() {
+            if let EventType::ButtonPressed(button, _) = event.event {
+                feed_ui(ui, button);
+                if let Some((_, action)) = BUTTONS.iter().find(|(b, _)| *b == button) {
+                    controls.press(*action);
+                }
+            }
+        }
+
+        let mut stick = None;
+        for (_, gamepad) in gilrs.gamepads() {
+            feed_gamepad(controls, &gamepad);
+            stick = stick.or(stick_direction(
+                gamepad.value(Axis::LeftStickX),
+                gamepad.value(Axis::LeftStickY),
+            ));
+        }
+
+        // Leaning the stick is one press, it has to come back before it moves focus again.
+        if let Some(button) = stick
+            && stick != self.stick
+        {
+            feed_ui(ui, button);
+        }
+        self.stick = stick;
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn feed_gamepad(controls: &mut Controls, gamepad: &Gamepad) {
+    let turn = dead_zone(gamepad.value(Axis::LeftStickX));
+    controls.feed(Action::TurnLeft, -turn);
+    controls.feed(Action::TurnRight, turn);
+
+    let strafe = dead_zone(gamepad.value(Axis::RightStickX));
+    controls.feed(Action::StrafeLeft, -strafe);
+    controls.feed(Action::StrafeRight, strafe);
+
+    let trigger = |button| gamepad.button_data(button).map_or(0., |b| b.value());
+    controls.feed(Action::Thrust, trigger(Button::RightTrigger2));
+    controls.feed(Action::Reverse, trigger(Button::LeftTrigger2));
+
+    for (button, action) in BUTTONS {
+        if gamepad.is_pressed(button) {
+            controls.feed(action, 1.);
+        }
+    }
+}
+
+fn feed_ui(ui: &mut UiInput, button: Button) {
+    match button {
+        Button::DPadUp => ui.up = true,
+        Button::DPadDown => ui.down = true,
+        Button::DPadLeft => ui.left = true,
+        Button::DPadRight => ui.right = true,
+        Button::South => ui.confirm = true,
+        Button::East => ui.back = true,
+        _ => {}
+    }
+}
+
+// The D-pad button a leaning stick stands in for, along whichever axis leans further.
+fn stick_direction(x: f32, y: f32) -> Option<Button> {
+    if x.abs().max(y.abs()) < STICK_STEP {
+        None
+    } else if x.abs() > y.abs() {
+        Some(if x > 0. {
+            Button::DPadRight
+        } else {
+            Button::DPadLeft
+        })
+    } else {
+        Some(if y > 0. {
+            Button::DPadUp
+        } else {
+            Button::DPadDown
+        })
+    }
+}
+
+// Maps the travel past the dead zone back onto the full -1..1 range.
+pub fn dead_zone(value: f32) -> f32 {
+    if value.abs() < DEAD_ZONE {
+        return 0.;
+    }
+    value.signum() * ((value.abs() - DEAD_ZONE) / (1. - DEAD_ZONE)).min(1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_zone_ignores_small_travel_and_keeps_the_full_range() {
+        assert_eq!(dead_zone(0.1), 0.);
+        assert_eq!(dead_zone(-0.1), 0.);
+        assert_eq!(dead_zone(1.), 1.);
+        assert_eq!(dead_zone(-1.), -1.);
+        assert!((dead_zone(0.575) - 0.5).abs() < 1e-4);
+    }
+
+    #[test]
+    fn analog_input_mixes_with_the_keyboard() {
+        let mut controls = Controls::new();
+        controls.feed(Action::TurnRight, 0.4);
+        controls.feed(Action::TurnLeft, -0.4);
+        assert!((controls.axis(Action::TurnLeft, Action::TurnRight) - 0.4).abs() < 1e-6);
+
+        // A fully held key beats a half pressed trigger.
+        controls.feed(Action::Thrust, 1.);
+        controls.feed(Action::Thrust, 0.5);
+        assert_eq!(controls.value(Action::Thrust), 1.);
+
+        controls.press(Action::Ping);
+        assert!(controls.was_pressed(Action::Ping));
+    }
+
+    #[test]
+    fn the_pad_drives_menus() {
+        let mut ui = UiInput::default();
+        feed_ui(&mut ui, Button::DPadDown);
+        feed_ui(&mut ui, Button::South);
+        feed_ui(&mut ui, Button::West);
+        assert!(ui.down && ui.confirm);
+        assert!(!ui.up && !ui.back);
+
+        feed_ui(&mut ui, Button::East);
+        assert!(ui.back);
+
+        // The stick steps along the axis it leans on most, gilrs' y points up.
+        assert_eq!(stick_direction(0.3, 0.2), None);
+        assert_eq!(stick_direction(0.2, 0.9), Some(Button::DPadUp));
+        assert_eq!(stick_direction(-0.8, 0.6), Some(Button::DPadLeft));
+        assert_eq!(stick_direction(0.1, -0.7), Some(Button::DPadDown));
+    }
+}
@@ -0,0 +1,68 @@
+use notan::math::Vec2;
+
+use crate::tuning::Tuning;
+
+// How far away each kind of noise can be heard, in pixels.
+pub struct NoiseTuning {
+    pub ping: f32,
+    pub impact: f32,
+    pub rockfall: f32,
+    pub torpedo: f32,
+    // Sleeping creatures are drawn towards noises at least this loud.
+    pub lure: f32,
+}
+
+impl NoiseTuning {
+    pub fn new(tuning: &Tuning) -> Self {
+        Self {
+            ping: tuning.get("noise.ping"),
+            impact: tuning.get("noise.impact"),
+            rockfall: tuning.get("noise.rockfall"),
+            torpedo: tuning.get("noise.torpedo"),
+            lure: tuning.get("noise.lure"),
+        }
+    }
+}
+
+// Hazard component. Each one covers a circle of `radius` around the entity.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Hazard {
+    // Water moving at `flow` pixels per frame in the middle, slowing towards the edge.
+    // Carries the sub and echoes along.
+    Current { radius: f32, flow: Vec2 },
+    // Hot water that wears down the hull and scatters echoes passing through.
+    Vent { radius: f32 },
+    // Loose rock that comes down once a loud enough noise reaches it.
+    Rockfall { radius: f32, fallen: bool },
+}
+
+impl Hazard {
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Hazard::Current { radius, .. }
+            | Hazard::Vent { radius }
+            | Hazard::Rockfall { radius, .. } => radius,
+        }
+    }
+
+    // 1 at the center down to 0 at the edge.
+    pub fn strength(&self, center: Vec2, pos: Vec2) -> f32 {
+        (1. - pos.distance(center) / self.radius()).max(0.)
+    }
+
+    pub fn flow(&self, center: Vec2, pos: Vec2) -> Vec2 {
+        match *self {
+            Hazard::Current { flow, .. } => flow * self.strength(center, pos),
+            _ => Vec2::ZERO,
+        }
+    }
+
+    pub fn is_hot(&self, center: Vec2, pos: Vec2) -> bool {
+        matches!(self, Hazard::Vent { .. }) && pos.distance(center) < self.radius()
+    }
+
+    // Whether a noise heard up to `loudness` away from `source` brings the rock down.
+    pub fn shaken(&self, center: Vec2, source: Vec2, loudness: f32) -> bool {
+        matches!(self, Hazard::Rockfall { fallen: false, .. }) && source.distance(center) < loudness
+    }
+}
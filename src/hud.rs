@@ -0,0 +1,223 @@
+use notan::{
+    app::Color,
+    draw::{Draw, DrawShapes, DrawTextSection, Font},
+    math::Vec2,
+};
+
+// The cave starts this far below the surface and every pixel is half a meter.
+const SURFACE_DEPTH: f32 = 3000.;
+const METERS_PER_PIXEL: f32 = 0.5;
+const CAVE_HEIGHT: f32 = 2216.;
+
+const MARGIN: f32 = 30.;
+const ACCENT: Color = Color::new(0.8, 0.0, 0.8, 1.0);
+const DIM: Color = Color::new(1.0, 1.0, 1.0, 0.25);
+const WARNING: Color = Color::new(1.0, 0.2, 0.2, 1.0);
+const GAUGE_SIZE: Vec2 = Vec2::new(160., 12.);
+
+pub struct HudInfo {
+    pub heading: f32,
+    pub echo_ready: f32,
+    pub beacons_left: i32,
+    pub max_beacons: i32,
+    pub torpedoes_left: i32,
+    pub max_torpedoes: i32,
+    pub y: f32,
+    pub speed: f32,
+    pub hull: f32,
+    pub power: f32,
+    pub oxygen: f32,
+    // Lowest tank among the divers following the sub.
+    pub friend_oxygen: Option<f32>,
+    // Divers reached and divers in the level.
+    pub rescues: (usize, usize),
+}
+
+pub fn depth(y: f32) -> f32 {
+    SURFACE_DEPTH + y * METERS_PER_PIXEL
+}
+
+pub struct Hud {
+    pub visible: bool,
+    pub text_scale: f32,
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            text_scale: 1.,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn render(&self, info: &HudInfo, font: &Font, width: f32, height: f32, draw: &mut Draw) {
+        if !self.visible {
+            return;
+        }
+
+        self.compass(info, font, draw);
+        self.beacons(info, font, height, draw);
+        self.depth_gauge(info, font, width, height, draw);
+
+        let (rescued, survivors) = info.rescues;
+        if survivors > 0 {
+            draw.text(font, &format!("RESCUED {rescued}/{survivors}"))
+                .size(20. * self.text_scale)
+                .color(Color::WHITE)
+                .h_align_center()
+                .position(width / 2., MARGIN);
+        }
+
+        // Gauges stack up from the bottom right corner.
+        let hull_color = if info.hull < 0.25 { WARNING } else { ACCENT };
+        let power_color = if info.power < 0.2 { WARNING } else { ACCENT };
+        let mut gauges = vec![
+            ("SPEED", info.speed, ACCENT),
+            ("HULL", info.hull, hull_color),
+            ("POWER", info.power, power_color),
+            ("OXYGEN", info.oxygen, oxygen_color(info.oxygen)),
+        ];
+        if let Some(friend) = info.friend_oxygen {
+            gauges.push(("DIVER OXYGEN", friend, oxygen_color(friend)));
+        }
+        for (row, (label, value, color)) in gauges.into_iter().enumerate() {
+            let pos = Vec2::new(
+                width - MARGIN - GAUGE_SIZE.x,
+                height - MARGIN - GAUGE_SIZE.y - row as f32 * 50. * self.text_scale,
+            );
+            self.gauge(label, value, color, pos, font, draw);
+        }
+    }
+
+    fn compass(&self, info: &HudInfo, font: &Font, draw: &mut Draw) {
+        let center = Vec2::splat(MARGIN + 60.);
+
+        draw.circle(45.)
+            .position(center.x, center.y)
+            .stroke_color(DIM)
+            .stroke(2.);
+
+        // The sonar cooldown fills up as a ring of dots, like the one around the sub.
+        let dots = 30;
+        let ready = (info.echo_ready * dots as f32) as i32;
+        for i in 0..dots {
+            let p = center + Vec2::from_angle((i as f32 * 12. - 90.).to_radians()) * 58.;
+            let color = if info.echo_ready >= 1. {
+                ACCENT
+            } else if i < ready {
+                Color::WHITE
+            } else {
+                DIM
+            };
+            draw.rect((p.x - 2., p.y - 2.), (4., 4.)).color(color);
+        }
+
+        let needle = center + Vec2::from_angle(info.heading.to_radians()) * 40.;
+        draw.line((center.x, center.y), (needle.x, needle.y))
+            .color(ACCENT)
+            .width(4.);
+
+        draw.text(font, "N")
+            .size(20. * self.text_scale)
+            .color(Color::WHITE)
+            .h_align_center()
+            .v_align_middle()
+            .position(center.x, center.y - 30.);
+    }
+
+    fn beacons(&self, info: &HudInfo, font: &Font, height: f32, draw: &mut Draw) {
+        draw.text(font, "BEACONS")
+            .size(20. * self.text_scale)
+            .color(Color::WHITE)
+            .v_align_bottom()
+            .position(MARGIN, height - MARGIN - 50.);
+
+        for i in 0..info.max_beacons {
+            let x = MARGIN + 20. + i as f32 * 40.;
+            let y = height - MARGIN - 20.;
+            let color = if i < info.beacons_left { ACCENT } else { DIM };
+
+            draw.circle(12.)
+                .position(x, y)
+                .stroke_color(color)
+                .stroke(4.);
+        }
+
+        if info.max_torpedoes == 0 {
+            return;
+        }
+
+        draw.text(font, "TORPEDOES")
+            .size(20. * self.text_scale)
+            .color(Color::WHITE)
+            .v_align_bottom()
+            .position(MARGIN, height - MARGIN - 120.);
+
+        for i in 0..info.max_torpedoes {
+            let x = MARGIN + 8. + i as f32 * 40.;
+            let y = height - MARGIN - 96.;
+            let color = if i < info.torpedoes_left { ACCENT } else { DIM };
+
+            draw.rect((x, y), (26., 8.)).color(color);
+        }
+    }
+
+    fn depth_gauge(&self, info: &HudInfo, font: &Font, width: f32, height: f32, draw: &mut Draw) {
+        let x = width - MARGIN - 10.;
+        let top = MARGIN + 40.;
+        let bottom = height * 0.6;
+
+        draw.rect((x, top), (10., bottom - top))
+            .stroke_color(DIM)
+            .stroke(2.);
+
+        let marker = top + (bottom - top) * (info.y / CAVE_HEIGHT).clamp(0., 1.);
+        draw.rect((x - 6., marker - 2.), (22., 4.)).color(ACCENT);
+
+        draw.text(font, &format!("{:.0}m", depth(info.y)))
+            .size(20. * self.text_scale)
+            .color(Color::WHITE)
+            .h_align_right()
+            .v_align_middle()
+            .position(x - 12., marker);
+    }
+
+    fn gauge(
+        &self,
+        label: &str,
+        value: f32,
+        color: Color,
+        pos: Vec2,
+        font: &Font,
+        draw: &mut Draw,
+    ) {
+        draw.rect((pos.x, pos.y), (GAUGE_SIZE.x, GAUGE_SIZE.y))
+            .stroke_color(DIM)
+            .stroke(2.);
+        draw.rect(
+            (pos.x, pos.y),
+            (GAUGE_SIZE.x * value.clamp(0., 1.), GAUGE_SIZE.y),
+        )
+        .color(color);
+
+        draw.text(font, label)
+            .size(20. * self.text_scale)
+            .color(Color::WHITE)
+            .v_align_bottom()
+            .position(pos.x, pos.y - 8.);
+    }
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn oxygen_color(fraction: f32) -> Color {
+    if fraction < 0.25 { WARNING } else { ACCENT }
+}
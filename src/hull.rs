@@ -0,0 +1,109 @@
+use crate::{material::Material, tuning::Tuning};
+
+pub struct HullTuning {
+    pub max: f32,
+    pub safe_speed: f32,
+    pub impact_damage: f32,
+    pub hazard_damage: f32,
+    pub monster_damage: f32,
+    pub monster_radius: f32,
+    pub vent_damage: f32,
+    pub rockfall_damage: f32,
+    pub grace: f32,
+}
+
+impl HullTuning {
+    pub fn new(tuning: &Tuning) -> Self {
+        Self {
+            max: tuning.get("hull.max"),
+            safe_speed: tuning.get("hull.safe_speed"),
+            impact_damage: tuning.get("hull.impact_damage"),
+            hazard_damage: tuning.get("hull.hazard_damage"),
+            monster_damage: tuning.get("hull.monster_damage"),
+            monster_radius: tuning.get("hull.monster_radius"),
+            vent_damage: tuning.get("hull.vent_damage"),
+            rockfall_damage: tuning.get("hull.rockfall_damage"),
+            grace: tuning.get("hull.grace"),
+        }
+    }
+}
+
+pub struct Hull {
+    pub health: f32,
+    pub tuning: HullTuning,
+    grace: f32,
+    // Fades from 1 to 0 after every hit, drives the red screen flash.
+    pub flash: f32,
+}
+
+impl Hull {
+    pub fn new(tuning: HullTuning) -> Self {
+        Self {
+            health: tuning.max,
+            tuning,
+            grace: 0.,
+            flash: 0.,
+        }
+    }
+
+    // Returns the damage taken, the sub keeps bouncing inside a wall for a few
+    // frames so hits during the grace period are ignored.
+    pub fn impact(&mut self, speed: f32, material: Material) -> f32 {
+        if self.grace > 0. {
+            return 0.;
+        }
+
+        let mut damage = (speed - self.tuning.safe_speed).max(0.)
+            * self.tuning.impact_damage
+            * material.hardness();
+        if material.is_hazard() {
+            damage += self.tuning.hazard_damage;
+        }
+
+        if damage > 0. {
+            self.damage(damage);
+            self.grace = self.tuning.grace;
+        }
+        damage
+    }
+
+    pub fn monster_contact(&mut self, dt: f32) {
+        self.damage(self.tuning.monster_damage * dt);
+    }
+
+    pub fn vent_contact(&mut self, dt: f32) {
+        self.damage(self.tuning.vent_damage * dt);
+    }
+
+    // Returns the damage taken from rocks coming down on the sub.
+    pub fn rockfall(&mut self) -> f32 {
+        self.damage(self.tuning.rockfall_damage);
+        self.tuning.rockfall_damage
+    }
+
+    pub fn repair(&mut self) {
+        self.health = self.tuning.max;
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.grace = (self.grace - dt * 60.).max(0.);
+        self.flash = (self.flash - dt * 2.).max(0.);
+    }
+
+    pub fn fraction(&self) -> f32 {
+        // A hull tuned to nothing is as good as broken.
+        if self.tuning.max <= 0. {
+            return 0.;
+        }
+        (self.health / self.tuning.max).clamp(0., 1.)
+    }
+
+    pub fn is_broken(&self) -> bool {
+        self.health <= 0.
+    }
+
+    fn damage(&mut self, amount: f32) {
+        self.health = (self.health - amount).max(0.);
+        self.flash = 1.;
+    }
+}
@@ -0,0 +1,182 @@
+use notan::{app::App, prelude::KeyCode};
+
+use crate::keys;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    Thrust,
+    Reverse,
+    TurnLeft,
+    TurnRight,
+    StrafeLeft,
+    StrafeRight,
+    Ping,
+    DropBeacon,
+    Torpedo,
+    Pause,
+}
+
+pub const ACTIONS: usize = 10;
+
+impl Action {
+    pub const ALL: [Action; ACTIONS] = [
+        Action::Thrust,
+        Action::Reverse,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::StrafeLeft,
+        Action::StrafeRight,
+        Action::Ping,
+        Action::DropBeacon,
+        Action::Torpedo,
+        Action::Pause,
+    ];
+
+    // Name used in the settings file and as the widget id in the controls menu.
+    pub fn key(self) -> &'static str {
+        match self {
+            Action::Thrust => "thrust",
+            Action::Reverse => "reverse",
+            Action::TurnLeft => "turn_left",
+            Action::TurnRight => "turn_right",
+            Action::StrafeLeft => "strafe_left",
+            Action::StrafeRight => "strafe_right",
+            Action::Ping => "ping",
+            Action::DropBeacon => "drop_beacon",
+            Action::Torpedo => "torpedo",
+            Action::Pause => "pause",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Action::Thrust => "THRUST",
+            Action::Reverse => "REVERSE",
+            Action::TurnLeft => "TURN LEFT",
+            Action::TurnRight => "TURN RIGHT",
+            Action::StrafeLeft => "STRAFE LEFT",
+            Action::StrafeRight => "STRAFE RIGHT",
+            Action::Ping => "SONAR PING",
+            Action::DropBeacon => "DROP BEACON",
+            Action::Torpedo => "FIRE TORPEDO",
+            Action::Pause => "PAUSE",
+        }
+    }
+
+    pub fn default_keys(self) -> &'static [KeyCode] {
+        match self {
+            Action::Thrust => &[KeyCode::W],
+            Action::Reverse => &[KeyCode::S],
+            Action::TurnLeft => &[KeyCode::A],
+            Action::TurnRight => &[KeyCode::D],
+            Action::StrafeLeft => &[KeyCode::Z],
+            Action::StrafeRight => &[KeyCode::X],
+            Action::Ping => &[KeyCode::Space],
+            Action::DropBeacon => &[KeyCode::B],
+            Action::Torpedo => &[KeyCode::F],
+            Action::Pause => &[KeyCode::Escape],
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|a| a.key() == key)
+    }
+}
+
+pub type Bindings = [Vec<KeyCode>; ACTIONS];
+
+pub fn default_bindings() -> Bindings {
+    Action::ALL.map(|action| action.default_keys().to_vec())
+}
+
+pub fn binding_names(keys: &[KeyCode]) -> String {
+    let names: Vec<&str> = keys.iter().map(|k| keys::name(*k)).collect();
+    names.join("/")
+}
+
+// Binds `key` to `action`, or unbinds it if it was already there, unless it is the
+// action's last key. The key is taken off other actions that have a key to spare, so
+// it can still be shared with an action it is the only key of. An action holds up to
+// three keys, binding a fourth drops the oldest.
+pub fn rebind(bindings: &mut Bindings, action: Action, key: KeyCode) {
+    let keys = &mut bindings[action as usize];
+    if let Some(i) = keys.iter().position(|k| *k == key) {
+        if keys.len() > 1 {
+            keys.remove(i);
+        }
+        return;
+    }
+
+    for (other, keys) in bindings.iter_mut().enumerate() {
+        if other != action as usize && keys.len() > 1 {
+            keys.retain(|k| *k != key);
+        }
+    }
+
+    let keys = &mut bindings[action as usize];
+    if keys.len() >= 3 {
+        keys.remove(0);
+    }
+    keys.push(key);
+}
+
+// Per-frame state of every action. Values are analog in 0..1 so sticks and triggers
+// can give partial input, the keyboard only ever gives 0 or 1. Gamepads add theirs
+// through `feed` and `press` after `update`.
+pub struct Controls {
+    values: [f32; ACTIONS],
+    pressed: [bool; ACTIONS],
+}
+
+impl Controls {
+    pub fn new() -> Self {
+        Self {
+            values: [0.; ACTIONS],
+            pressed: [false; ACTIONS],
+        }
+    }
+
+    pub fn update(&mut self, app: &App, bindings: &Bindings) {
+        for action in Action::ALL {
+            let keys = &bindings[action as usize];
+            let down = keys.iter().any(|k| app.keyboard.is_down(*k));
+
+            self.values[action as usize] = if down { 1. } else { 0. };
+            self.pressed[action as usize] = keys.iter().any(|k| app.keyboard.was_pressed(*k));
+        }
+    }
+
+    // Lets another device report an analog value, the strongest source wins.
+    pub fn feed(&mut self, action: Action, value: f32) {
+        let current = &mut self.values[action as usize];
+        *current = current.max(value.clamp(0., 1.));
+    }
+
+    // Lets another device report a press this frame.
+    pub fn press(&mut self, action: Action) {
+        self.pressed[action as usize] = true;
+    }
+
+    pub fn value(&self, action: Action) -> f32 {
+        self.values[action as usize]
+    }
+
+    pub fn is_down(&self, action: Action) -> bool {
+        self.value(action) > 0.
+    }
+
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed[action as usize]
+    }
+
+    // A -1..1 axis from two opposing actions.
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.value(positive) - self.value(negative)
+    }
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        Self::new()
+    }
+}
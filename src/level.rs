@@ -0,0 +1,314 @@
+use notan::math::Vec2;
+
+use crate::{
+    entity::{Entity, SurvivorCondition},
+    lore::{LogEntry, LogKind},
+    material::Material,
+    terrain::Region,
+    trigger::{self, Condition, Shape, Trigger},
+};
+
+// Level data is a plain text file, one entry per line starting with a keyword.
+// See assets/level.txt for the format of each entry.
+
+// Progress is snapshotted when the player first comes within `radius` of `pos`.
+#[derive(Clone, Debug)]
+pub struct Checkpoint {
+    pub pos: Vec2,
+    pub radius: f32,
+    pub name: String,
+    pub reached: bool,
+}
+
+// Something the player picks up by touching it, once.
+#[derive(Clone, Debug)]
+pub struct Pickup {
+    pub pos: Vec2,
+    pub amount: f32,
+    pub taken: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct AirPocket {
+    pub pos: Vec2,
+    pub radius: f32,
+}
+
+pub struct Level {
+    pub triggers: Vec<Trigger>,
+    pub checkpoints: Vec<Checkpoint>,
+    pub power_cells: Vec<Pickup>,
+    pub salvage: Vec<Pickup>,
+    pub air_pockets: Vec<AirPocket>,
+    pub entities: Vec<Entity>,
+    pub logs: Vec<LogEntry>,
+    // Materials the torpedo can break, the rest of the cave holds.
+    pub breakable: Vec<Material>,
+    // Passages filled in before the level starts.
+    pub walls: Vec<(Region, Material)>,
+}
+
+impl Level {
+    pub fn load() -> Self {
+        Self::parse(include_str!("assets/level.txt")).unwrap()
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut level = Self {
+            triggers: vec![],
+            checkpoints: vec![],
+            power_cells: vec![],
+            salvage: vec![],
+            air_pockets: vec![],
+            entities: vec![],
+            logs: vec![],
+            breakable: vec![],
+            walls: vec![],
+        };
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            let fields: Vec<&str> = rest.split(';').map(str::trim).collect();
+
+            match keyword {
+                "message" => parse_message(&fields).map(|t| level.triggers.push(t)),
+                "trigger" => trigger::parse_trigger(&fields).map(|t| level.triggers.push(t)),
+                "checkpoint" => parse_checkpoint(&fields).map(|c| level.checkpoints.push(c)),
+                "power_cell" => parse_pickup(&fields).map(|p| level.power_cells.push(p)),
+                "salvage" => parse_pickup(&fields).map(|p| level.salvage.push(p)),
+                "air_pocket" => parse_air_pocket(&fields).map(|a| level.air_pockets.push(a)),
+                "survivor" => parse_survivor(&fields).map(|s| level.entities.push(s)),
+                "creature" => {
+                    parse_position(&fields).map(|pos| level.entities.push(Entity::creature(pos)))
+                }
+                "beacon" => parse_beacon(&fields).map(|b| level.entities.push(b)),
+                "current" => parse_current(&fields).map(|c| level.entities.push(c)),
+                "vent" => parse_area(&fields).map(|(pos, radius)| {
+                    level.entities.push(Entity::vent(pos, radius));
+                }),
+                "rockfall" => parse_area(&fields).map(|(pos, radius)| {
+                    level.entities.push(Entity::rockfall(pos, radius));
+                }),
+                "breakable" => parse_material(rest).map(|m| level.breakable.push(m)),
+                "wall" => parse_wall(&fields).map(|w| level.walls.push(w)),
+                "log" => parse_log(&fields).map(|(pos, log)| {
+                    level.entities.push(Entity::log(pos, level.logs.len()));
+                    level.logs.push(log);
+                }),
+                _ => Err(format!("unknown entry `{keyword}`")),
+            }
+            .map_err(|e| format!("level line {}: {e}", i + 1))?;
+        }
+
+        Ok(level)
+    }
+}
+
+pub fn parse_numbers(text: &str) -> Result<Vec<f32>, String> {
+    text.split_whitespace()
+        .map(|n| {
+            n.parse::<f32>()
+                .map_err(|_| format!("`{n}` is not a number"))
+        })
+        .collect()
+}
+
+// Shorthand for a trigger that shows a message once.
+fn parse_message(fields: &[&str]) -> Result<Trigger, String> {
+    let [when, message @ ..] = fields else {
+        return Err("a message needs 5 fields".to_string());
+    };
+
+    let (kind, args) = when.split_once(' ').unwrap_or((when, ""));
+    let (shape, conditions) = match (kind, parse_numbers(args)?.as_slice()) {
+        ("start", []) => (Shape::Anywhere, vec![]),
+        ("friend_found", []) => (Shape::Anywhere, vec![Condition::FriendFound(true)]),
+        ("near", [x, y, radius]) => (Shape::Circle(Vec2::new(*x, *y), *radius), vec![]),
+        _ => return Err(format!("bad trigger `{when}`")),
+    };
+
+    let message = trigger::parse_message(message)?;
+    Ok(Trigger::new(
+        shape,
+        conditions,
+        false,
+        trigger::TriggerAction::Message(message),
+    ))
+}
+
+fn parse_checkpoint(fields: &[&str]) -> Result<Checkpoint, String> {
+    let [area, name] = fields else {
+        return Err("a checkpoint needs 2 fields".to_string());
+    };
+
+    let [x, y, radius] = parse_numbers(area)?[..] else {
+        return Err(format!("bad checkpoint area `{area}`"));
+    };
+
+    Ok(Checkpoint {
+        pos: Vec2::new(x, y),
+        radius,
+        name: name.to_string(),
+        reached: false,
+    })
+}
+
+fn parse_pickup(fields: &[&str]) -> Result<Pickup, String> {
+    let [pos, amount] = fields else {
+        return Err("a pickup needs 2 fields".to_string());
+    };
+
+    let [x, y] = parse_numbers(pos)?[..] else {
+        return Err(format!("bad pickup position `{pos}`"));
+    };
+
+    let amount = amount
+        .parse::<f32>()
+        .map_err(|_| format!("bad amount `{amount}`"))?;
+
+    Ok(Pickup {
+        pos: Vec2::new(x, y),
+        amount,
+        taken: false,
+    })
+}
+
+fn parse_air_pocket(fields: &[&str]) -> Result<AirPocket, String> {
+    let [area] = fields else {
+        return Err("an air pocket needs 1 field".to_string());
+    };
+
+    let [x, y, radius] = parse_numbers(area)?[..] else {
+        return Err(format!("bad air pocket area `{area}`"));
+    };
+
+    Ok(AirPocket {
+        pos: Vec2::new(x, y),
+        radius,
+    })
+}
+
+fn parse_position(fields: &[&str]) -> Result<Vec2, String> {
+    let [pos] = fields else {
+        return Err("expected just a position".to_string());
+    };
+
+    let [x, y] = parse_numbers(pos)?[..] else {
+        return Err(format!("bad position `{pos}`"));
+    };
+
+    Ok(Vec2::new(x, y))
+}
+
+fn parse_survivor(fields: &[&str]) -> Result<Entity, String> {
+    let (pos, condition) = match fields {
+        [pos] => (parse_position(&[pos])?, SurvivorCondition::Calm),
+        [pos, condition] => (
+            parse_position(&[pos])?,
+            SurvivorCondition::from_key(condition)
+                .ok_or(format!("bad survivor condition `{condition}`"))?,
+        ),
+        _ => return Err("a survivor needs 1 or 2 fields".to_string()),
+    };
+
+    Ok(Entity::survivor(pos, condition))
+}
+
+fn parse_beacon(fields: &[&str]) -> Result<Entity, String> {
+    let [pos, freq, visibility] = fields else {
+        return Err("a beacon needs 3 fields".to_string());
+    };
+
+    let [x, y] = parse_numbers(pos)?[..] else {
+        return Err(format!("bad beacon position `{pos}`"));
+    };
+
+    let freq = freq
+        .parse::<i32>()
+        .map_err(|_| format!("bad frequency `{freq}`"))?;
+
+    let visible = match *visibility {
+        "visible" => true,
+        "hidden" => false,
+        _ => return Err(format!("bad beacon visibility `{visibility}`")),
+    };
+
+    Ok(Entity::beacon(Vec2::new(x, y), visible, freq))
+}
+
+fn parse_area(fields: &[&str]) -> Result<(Vec2, f32), String> {
+    let [area] = fields else {
+        return Err("expected just an area".to_string());
+    };
+
+    let [x, y, radius] = parse_numbers(area)?[..] else {
+        return Err(format!("bad area `{area}`"));
+    };
+
+    Ok((Vec2::new(x, y), radius))
+}
+
+fn parse_current(fields: &[&str]) -> Result<Entity, String> {
+    let [area, flow] = fields else {
+        return Err("a current needs 2 fields".to_string());
+    };
+
+    let (pos, radius) = parse_area(&[area])?;
+    let [dx, dy] = parse_numbers(flow)?[..] else {
+        return Err(format!("bad flow `{flow}`"));
+    };
+
+    Ok(Entity::current(pos, radius, Vec2::new(dx, dy)))
+}
+
+fn parse_material(text: &str) -> Result<Material, String> {
+    Material::from_key(text.trim()).ok_or(format!("bad material `{text}`"))
+}
+
+fn parse_wall(fields: &[&str]) -> Result<(Region, Material), String> {
+    let [area, material] = fields else {
+        return Err("a wall needs 2 fields".to_string());
+    };
+
+    let [x0, y0, x1, y1] = parse_numbers(area)?[..] else {
+        return Err(format!("bad wall area `{area}`"));
+    };
+    if x0 < 0. || y0 < 0. || x1 <= x0 || y1 <= y0 {
+        return Err(format!("bad wall area `{area}`"));
+    }
+
+    let region = Region {
+        x0: x0 as usize,
+        y0: y0 as usize,
+        x1: x1 as usize,
+        y1: y1 as usize,
+    };
+    Ok((region, parse_material(material)?))
+}
+
+fn parse_log(fields: &[&str]) -> Result<(Vec2, LogEntry), String> {
+    let [pos, key, kind, title, text] = fields else {
+        return Err("a log needs 5 fields".to_string());
+    };
+
+    let pos = parse_position(&[pos])?;
+    let kind = LogKind::from_key(kind).ok_or(format!("bad log kind `{kind}`"))?;
+    if key.is_empty() || key.contains(char::is_whitespace) {
+        return Err(format!("bad log key `{key}`"));
+    }
+
+    Ok((
+        pos,
+        LogEntry {
+            key: key.to_string(),
+            kind,
+            title: title.to_string(),
+            text: text.to_string(),
+        },
+    ))
+}
@@ -0,0 +1,43 @@
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LogKind {
+    Recorder,
+    Diver,
+}
+
+impl LogKind {
+    pub fn key(self) -> &'static str {
+        match self {
+            LogKind::Recorder => "recorder",
+            LogKind::Diver => "diver",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LogKind::Recorder => "BLACK BOX",
+            LogKind::Diver => "DIVER LOG",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<LogKind> {
+        [LogKind::Recorder, LogKind::Diver]
+            .into_iter()
+            .find(|k| k.key() == key)
+    }
+}
+
+// A story fragment placed in the level. `key` is what the profile remembers, so it
+// should stay the same when the text is edited.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogEntry {
+    pub key: String,
+    pub kind: LogKind,
+    pub title: String,
+    pub text: String,
+}
+
+impl LogEntry {
+    pub fn heading(&self) -> String {
+        format!("{}: {}", self.kind.label(), self.title)
+    }
+}
@@ -1,25 +1,79 @@
+pub mod battery;
 pub mod beacon;
 pub mod camera;
+pub mod config;
 pub mod echo;
-pub mod friend;
-pub mod monster;
+pub mod entity;
+pub mod events;
+pub mod gamepad;
+pub mod hazard;
+pub mod hud;
+pub mod hull;
+pub mod input;
+pub mod keys;
+pub mod level;
+pub mod lore;
+pub mod material;
+pub mod menus;
+pub mod message;
+pub mod minimap;
+pub mod oxygen;
 pub mod player;
-
-use beacon::Beacon;
+pub mod save;
+pub mod score;
+pub mod sdf;
+pub mod settings;
+pub mod sound;
+pub mod spatial;
+pub mod synth;
+pub mod tension;
+pub mod terrain;
+pub mod trigger;
+pub mod tuning;
+pub mod ui;
+pub mod upgrades;
+
+use battery::{Battery, BatteryTuning};
 use camera::Camera2D;
 use echo::Echo;
-use friend::Friend;
-use monster::Monster;
+use entity::{Ai, Entities, Entity, Kind};
+use events::{DamageSource, Event, EventBus};
+use gamepad::Gamepads;
+use hazard::{Hazard, NoiseTuning};
+use hud::{Hud, HudInfo};
+use hull::{Hull, HullTuning};
+use input::{Action, Controls};
+use level::{AirPocket, Checkpoint, Level, Pickup};
+use lore::LogEntry;
+use material::Material;
+use menus::{MenuAction, Menus, Screen};
+use message::{Message, MessageQueue, Priority};
+use minimap::{MapMarker, Minimap};
 use notan::draw::*;
 use notan::log;
 use notan::math::Vec2;
 use notan::prelude::*;
+use oxygen::{Oxygen, OxygenTuning};
 use player::Player;
+use save::{SaveData, Slot};
+use score::{RunSummary, Score};
+use sdf::DistanceField;
+use settings::Settings;
+use sound::{Bus, SoundSystem};
+use spatial::Cue;
+use tension::{TensionDirector, TensionInput};
+use terrain::Terrain;
+use trigger::{Trigger, TriggerAction, TriggerContext};
+use tuning::Tuning;
+use ui::UiInput;
+use upgrades::{Profile, RESCUE_REWARD, SubStats};
 
 const RENDER_WIDTH: f32 = 320.;
 const RENDER_HEIGHT: f32 = 180.;
+// How much a torpedo breaks when it goes off.
+const TORPEDO_RADIUS: f32 = 28.;
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Scene {
     Start,
     Game,
@@ -29,6 +83,8 @@ pub enum Scene {
 struct DeathScene {
     pub fade: f32,
     pub show_text: bool,
+    pub text: String,
+    pub score: Option<Score>,
 }
 
 impl DeathScene {
@@ -36,57 +92,8 @@ impl DeathScene {
         Self {
             fade: 0.,
             show_text: false,
-        }
-    }
-}
-
-struct SoundSystem {
-    pub sound_effects: [AudioSource; 8],
-    sound: [Option<Sound>; 8],
-    repeat: [bool; 8],
-    volume: [f32; 8],
-}
-
-impl SoundSystem {
-    pub fn new(app: &mut App) -> Self {
-        let m0 = app
-            .audio
-            .create_source(include_bytes!("assets/ambiance.mp3"))
-            .unwrap();
-        let m1 = app
-            .audio
-            .create_source(include_bytes!("assets/jumpscare.mp3"))
-            .unwrap();
-        let m2 = app
-            .audio
-            .create_source(include_bytes!("assets/creepy_cave.mp3"))
-            .unwrap();
-        let m3 = app
-            .audio
-            .create_source(include_bytes!("assets/monster.mp3"))
-            .unwrap();
-        let m4 = app
-            .audio
-            .create_source(include_bytes!("assets/echo_scary.wav"))
-            .unwrap();
-        let m5 = app
-            .audio
-            .create_source(include_bytes!("assets/more_scary.wav"))
-            .unwrap();
-        let m6 = app
-            .audio
-            .create_source(include_bytes!("assets/scary_sound.mp3"))
-            .unwrap();
-        let m7 = app
-            .audio
-            .create_source(include_bytes!("assets/short_scary.wav"))
-            .unwrap();
-
-        Self {
-            sound_effects: [m0, m1, m2, m3, m4, m5, m6, m7],
-            sound: [None, None, None, None, None, None, None, None],
-            repeat: [true, false, false, false, false, false, false, false],
-            volume: [1., 1., 1., 1., 1., 1., 1., 1.],
+            text: "The end...".to_string(),
+            score: None,
         }
     }
 }
@@ -97,50 +104,61 @@ struct State {
     player_world_rtex: RenderTexture,
     cave_texture: Texture,
     player: Player,
-    cave_bytes: Vec<u8>,
+    hull: Hull,
+    battery: Battery,
+    oxygen: Oxygen,
+    noise: NoiseTuning,
+    terrain: Terrain,
+    field: DistanceField,
     echoes: Vec<Echo>,
-    beacons: Vec<Beacon>,
-    friend: Friend,
+    entities: Entities,
     num_of_beacons: i32,
+    num_of_torpedoes: i32,
+    // Materials torpedoes can break, and where they went off this run.
+    breakable: Vec<Material>,
+    blasts: Vec<(Vec2, f32)>,
     font: Font,
-    monster: Monster,
     scene: Scene,
     death_scene: DeathScene,
     sound_system: SoundSystem,
     music_start: bool,
     music_delay: i32,
-    played_random_time: i32,
-    show_found_text: bool,
-    found_text_timer: f32,
-    show_hint: bool,
-    show_hint_timer: f32,
-}
-
-fn play_music(index: usize, app: &mut App, sound_system: &mut SoundSystem) {
-    let sound = app.audio.play_sound(
-        &sound_system.sound_effects[index],
-        sound_system.volume[index],
-        sound_system.repeat[index],
-    );
-    sound_system.sound[index] = Some(sound);
-}
-
-fn is_playing(index: usize, app: &mut App, sound_system: &mut SoundSystem) -> bool {
-    match &sound_system.sound[index] {
-        Some(s) => !app.audio.is_stopped(s),
-        None => false,
-    }
+    tension: TensionDirector,
+    messages: MessageQueue,
+    hud: Hud,
+    minimap: Minimap,
+    menus: Menus,
+    settings: Settings,
+    profile: Profile,
+    controls: Controls,
+    gamepads: Gamepads,
+    triggers: Vec<Trigger>,
+    // Named items handed out by triggers, checked by their conditions.
+    items: Vec<String>,
+    checkpoints: Vec<Checkpoint>,
+    power_cells: Vec<Pickup>,
+    salvage: Vec<Pickup>,
+    air_pockets: Vec<AirPocket>,
+    // Text for the log entities, indexed by their `lore` component.
+    logs: Vec<LogEntry>,
+    // Snapshot of the last checkpoint reached, restored when the player is caught.
+    checkpoint: Option<SaveData>,
+    events: EventBus,
+    // Seconds spent in the cave this run.
+    run_time: f32,
 }
 
 #[notan_main]
 fn main() -> Result<(), String> {
-    notan::init_with(init)
-        .add_config(
-            WindowConfig::new()
-                .set_vsync(true)
-                .set_size(1440, 810)
-                .set_multisampling(0),
-        )
+    let settings = Settings::load();
+    let window = WindowConfig::new()
+        .set_vsync(settings.vsync)
+        .set_size(settings.resolution.0, settings.resolution.1)
+        .set_fullscreen(settings.fullscreen)
+        .set_multisampling(0);
+
+    notan::init_with(move |app: &mut App, gfx: &mut Graphics| init(app, gfx, settings))
+        .add_config(window)
         .add_config(log::LogConfig::debug())
         .update(update)
         .draw(draw)
@@ -148,11 +166,13 @@ fn main() -> Result<(), String> {
         .build()
 }
 
-fn init(app: &mut App, gfx: &mut Graphics) -> State {
+fn init(app: &mut App, gfx: &mut Graphics, settings: Settings) -> State {
     let mut camera = Camera2D::new(0., 0., RENDER_WIDTH * 2., RENDER_HEIGHT * 2.);
     camera.set_zoom(1.);
 
-    let sound_system = SoundSystem::new(app);
+    let sound_system = SoundSystem::new(app, settings.volumes);
+    let profile = Profile::load();
+    let mut menus = Menus::new(&settings, &profile);
 
     let cave_texture = gfx
         .create_texture()
@@ -161,7 +181,28 @@ fn init(app: &mut App, gfx: &mut Graphics) -> State {
         .build()
         .unwrap();
 
-    let cave_bytes = load_bytes(&cave_texture, gfx);
+    let mut terrain = Terrain::new(
+        load_bytes(&cave_texture, gfx),
+        cave_texture.width() as usize,
+        cave_texture.height() as usize,
+    );
+
+    let level = Level::load();
+    for &(region, material) in &level.walls {
+        terrain.fill_region(region, material);
+    }
+    terrain.bake();
+    menus.refresh_journal(&profile, &level.logs);
+    let tuning = Tuning::load();
+    let minimap = Minimap::new(gfx, cave_texture.width(), cave_texture.height());
+
+    let mut messages = MessageQueue::new();
+    messages.text_scale = settings.text_scale;
+    let mut hud = Hud::new();
+    hud.text_scale = settings.text_scale;
+
+    let mut events = EventBus::new();
+    events.subscribe(|event| log::debug!("event: {event:?}"));
 
     State {
         camera,
@@ -172,72 +213,147 @@ fn init(app: &mut App, gfx: &mut Graphics) -> State {
             .unwrap(),
         cave_texture,
         player: Player::new(gfx),
-        cave_bytes,
+        hull: Hull::new(HullTuning::new(&tuning)),
+        battery: Battery::new(BatteryTuning::new(&tuning)),
+        oxygen: Oxygen::new(OxygenTuning::new(&tuning)),
+        noise: NoiseTuning::new(&tuning),
+        field: DistanceField::new(&terrain),
+        terrain,
         echoes: vec![],
-        beacons: vec![
-            Beacon::new(885., 165., false, 15),
-            Beacon::new(880., 625., false, 15),
-            Beacon::new(227., 143., false, 15),
-            Beacon::new(1803., 143., false, 15),
-        ],
-        friend: Friend::new(gfx, 1825., 1080.),
-        num_of_beacons: 3,
+        entities: Entities::new(gfx, level.entities),
+        num_of_beacons: 0,
+        num_of_torpedoes: 0,
+        breakable: level.breakable,
+        blasts: vec![],
         font: gfx
             .create_font(include_bytes!("assets/slkscr.ttf"))
             .unwrap(),
-        monster: Monster::new(gfx, 1268., 460.),
         scene: Scene::Start,
         death_scene: DeathScene::new(),
         sound_system,
         music_start: false,
         music_delay: 0,
-        played_random_time: 0,
-        show_found_text: false,
-        found_text_timer: 250.,
-        show_hint: false,
-        show_hint_timer: 250.,
+        tension: TensionDirector::new(),
+        messages,
+        hud,
+        minimap,
+        menus,
+        settings,
+        profile,
+        controls: Controls::new(),
+        gamepads: Gamepads::new(),
+        triggers: level.triggers,
+        items: vec![],
+        checkpoints: level.checkpoints,
+        power_cells: level.power_cells,
+        salvage: level.salvage,
+        air_pockets: level.air_pockets,
+        logs: level.logs,
+        checkpoint: None,
+        events,
+        run_time: 0.,
     }
 }
 
 fn update(app: &mut App, state: &mut State) {
+    state.entities.sweep();
+    sync_terrain(state);
+    state.sound_system.update(app);
+
+    let mut input = UiInput::read(app);
+    state.controls.update(app, &state.settings.bindings);
+    state.gamepads.update(&mut state.controls, &mut input);
+
+    if state.menus.is_open() {
+        let width = app.window().width() as f32;
+        let height = app.window().height() as f32;
+
+        if let Some(action) = state
+            .menus
+            .update(&input, &mut state.settings, width, height)
+        {
+            match action {
+                MenuAction::Start => {
+                    state.player.stats = state.profile.stats();
+                    state.num_of_beacons = state.player.stats.beacons;
+                    state.num_of_torpedoes = state.player.stats.torpedoes;
+                    state.music_start = true;
+                    set_scene(state, Scene::Game);
+                    state.sound_system.mixer.set_fade(0.);
+                    state.sound_system.mixer.fade_to(1., 180.);
+                    state.checkpoint = Some(SaveData::capture(state));
+                }
+                MenuAction::Resume => {}
+                MenuAction::Quit => app.exit(),
+                MenuAction::Save(slot) => save::write(slot, &SaveData::capture(state)),
+                MenuAction::Load(slot) => load_game(state, slot),
+                MenuAction::Buy(upgrade) => {
+                    state.profile.buy(upgrade);
+                    state.menus.refresh_upgrades(&state.profile);
+                }
+                MenuAction::SettingsChanged => apply_settings(app, state),
+                MenuAction::SettingsClosed => state.settings.save(),
+            }
+
+            return;
+        }
+    } else if state.scene == Scene::Game && state.controls.was_pressed(Action::Pause) {
+        state.menus.open(Screen::Pause);
+    }
+
+    if state.scene == Scene::Game && state.menus.is_open() {
+        return;
+    }
+
     if state.music_start {
         state.music_delay += 1;
     }
 
     if state.music_delay >= 30 {
-        play_music(0, app, &mut state.sound_system);
+        // Ambience plus the two tension layers, which start silent.
+        for index in [0, 8, 9] {
+            if !state.sound_system.is_playing(index, app) {
+                state.sound_system.play(index, app);
+            }
+        }
         state.music_delay = 0;
         state.music_start = false;
     }
 
-    if app.timer.elapsed_f32() as i32 % 30 == 0
-        && state.scene == Scene::Game
-        && state.played_random_time != app.timer.elapsed_f32() as i32
-    {
-        if fastrand::bool() {
-            play_music(fastrand::i32(2..8) as usize, app, &mut state.sound_system);
-        }
-
-        state.played_random_time = app.timer.elapsed_f32() as i32;
-    }
-
-    if state.player.pos.distance(Vec2::new(900., 1600.)) < 100. {
-        state.show_hint = true;
-    }
-
-    state.player.update(
+    state.player.thrust_power = state.battery.thrust_power();
+    state.player.sonar_range = state.battery.sonar_range();
+    let pinged = state.player.update(
         app,
-        &state.cave_bytes,
+        &state.controls,
+        &state.terrain,
+        &state.field,
         &mut state.echoes,
         &state.camera,
         &state.scene,
     );
 
-    if state.monster.activated {
-        if state.scene != Scene::End {
-            play_music(1, app, &mut state.sound_system);
-        }
-        state.scene = Scene::End;
+    state.sound_system.listener = state.player.pos + 16.;
+    if pinged {
+        state.battery.ping(state.player.sonar_range);
+        state
+            .sound_system
+            .play_at(Cue::Ping, state.sound_system.listener, app);
+        state.tension.revealed();
+        state.events.emit(Event::Noise {
+            pos: state.sound_system.listener,
+            loudness: state.noise.ping,
+        });
+    }
+
+    // Left alone, the sub drifts at the speed of the water around it.
+    let flow = state.entities.flow_at(state.player.pos + 16.);
+    state.player.vel += flow / 100. * app.timer.delta_f32() * 60.;
+
+    if state.scene == Scene::Game {
+        state.run_time += app.timer.delta_f32();
+        update_hull(app, state);
+        update_battery(app, state);
+        update_oxygen(app, state);
     }
 
     state
@@ -246,20 +362,33 @@ fn update(app: &mut App, state: &mut State) {
 
     if !state.echoes.is_empty() {
         for echo in &mut state.echoes {
-            echo.update(&state.cave_bytes, app, &state.camera);
-
-            if !echo.hit {
-                if echo.pos.distance(state.friend.pos) < 16. {
-                    state.friend.show = true;
-                }
+            let was_hit = echo.hit;
+            state.entities.disturb(echo, app.timer.delta_f32());
+            echo.update(&state.terrain, &state.field, app, &state.camera);
+            let entity = state.entities.reflect(echo, &mut state.events);
+
+            if echo.hit && !was_hit {
+                state.events.emit(Event::EchoHit {
+                    pos: echo.pos,
+                    color: echo.hit_color,
+                    audible: echo.audible,
+                    entity,
+                });
             }
         }
     }
 
-    if state.friend.found {
-        state.show_found_text = true;
+    if state.scene == Scene::Game {
+        reach_checkpoints(state);
+        state
+            .entities
+            .touch(state.player.pos + 16., &mut state.events);
+
+        run_triggers(app, state);
     }
 
+    state.messages.update(app.timer.delta_f32());
+
     let mut i = 0;
     while i < state.echoes.len() {
         if state.echoes[i].lifetime <= 0. {
@@ -269,42 +398,259 @@ fn update(app: &mut App, state: &mut State) {
         i += 1;
     }
 
+    state.entities.update_ai(
+        state.player.pos,
+        state.player.vel.length(),
+        &state.field,
+        &mut state.events,
+    );
+    state.entities.integrate(app.timer.delta_f32());
     state
-        .monster
-        .update(app, &state.player.pos, state.friend.found);
+        .entities
+        .detonate(&state.terrain, app.timer.delta_f32(), &mut state.events);
 
-    state.friend.update(app, &state.player.pos);
+    if state.controls.was_pressed(Action::DropBeacon)
+        && state.num_of_beacons > 0
+        && state.battery.can_place_beacon()
+    {
+        state.battery.place_beacon();
+        state
+            .entities
+            .spawn(Entity::beacon(state.player.pos, true, 60));
+        state.num_of_beacons -= 1;
+        let pos = state.player.pos;
+        state.events.emit(Event::BeaconPlaced { pos });
+    }
 
-    if app.keyboard.was_pressed(KeyCode::B) && state.num_of_beacons > 0 {
-        state.beacons.push(Beacon::new(
-            state.player.pos.x,
-            state.player.pos.y,
-            true,
-            60,
+    if state.scene == Scene::Game
+        && state.controls.was_pressed(Action::Torpedo)
+        && state.num_of_torpedoes > 0
+    {
+        let dir = state.player.dir.to_radians();
+        let pos = state.player.pos + 16. + Vec2::from_angle(dir) * 20.;
+        state.entities.spawn(Entity::torpedo(
+            pos,
+            dir,
+            state.player.vel.length() + 5.,
+            TORPEDO_RADIUS,
         ));
-        state.num_of_beacons -= 1;
+        state.num_of_torpedoes -= 1;
+        state.sound_system.play_at(Cue::Ping, pos, app);
+    }
+
+    if state.scene == Scene::Game {
+        if app.keyboard.was_pressed(KeyCode::H) {
+            state.hud.toggle();
+        }
+
+        state.minimap.update(app);
+    }
+
+    let pinged = state
+        .entities
+        .update_emitters(&mut state.echoes, app.timer.delta_f32());
+    for pos in pinged {
+        state.sound_system.play_at(Cue::Beacon, pos, app);
     }
 
-    for beacon in &mut state.beacons {
-        beacon.update(&mut state.echoes, app.timer.delta_f32());
+    if state.scene != Scene::Start {
+        // Only a creature on the hunt gives itself away, a sleeping one stays silent.
+        for creature in state
+            .entities
+            .of_kind(Kind::Creature)
+            .filter(|c| c.is_awake())
+        {
+            state
+                .sound_system
+                .play_at(Cue::Growl, creature.center(), app);
+        }
+    }
+
+    if state.scene == Scene::Game {
+        let listener = state.sound_system.listener;
+        let lit = state
+            .echoes
+            .iter()
+            .filter(|e| e.hit && e.hit_color.a > 0.2 && e.pos.distance(listener) < 160.)
+            .count();
+
+        let input = TensionInput {
+            monster_distance: state
+                .entities
+                .nearest(Kind::Creature, listener)
+                .map_or(f32::INFINITY, |c| listener.distance(c.center())),
+            monster_awake: state.entities.awake_creature().is_some(),
+            friend_found: state.entities.any_found(),
+            darkness: 1. - (lit as f32 / 40.).min(1.),
+        };
+
+        if state.tension.update(&input, app.timer.delta_f32()) {
+            state.sound_system.play(fastrand::i32(2..8) as usize, app);
+        }
+
+        for layer in 0..2 {
+            let volume = state.tension.layer_volume(layer);
+            state.sound_system.set_volume(8 + layer, volume);
+        }
     }
 
     if state.scene == Scene::End {
-        if state.player.pos.distance(state.monster.pos) < 400. {
-            state.death_scene.fade =
-                map(state.monster.pos.x - state.player.pos.x, 0., 400., 1., 0.);
+        update_chase(app, state);
+
+        if let Some(monster) = state.entities.awake_creature() {
+            let monster = monster.pos();
+            if state.player.pos.distance(monster) < 400. {
+                state.death_scene.fade = map(monster.x - state.player.pos.x, 0., 400., 1., 0.);
+            }
+
+            if monster.x < state.player.pos.x {
+                state.death_scene.fade = 1.;
+            }
+
+            if monster.x < -600. {
+                state.death_scene.show_text = true;
+            }
+        } else {
+            state.death_scene.fade = (state.death_scene.fade + app.timer.delta_f32() * 0.5).min(1.);
+            if state.death_scene.fade >= 1. {
+                state.death_scene.show_text = true;
+            }
+        }
+
+        if state.death_scene.show_text
+            && input.confirm
+            && let Some(checkpoint) = state.checkpoint.clone()
+        {
+            respawn(state, &checkpoint);
         }
+    }
 
-        if state.monster.pos.x < state.player.pos.x {
-            state.death_scene.fade = 1.;
+    dispatch_events(app, state);
+}
+
+// Reactions to events, run once per frame after everything has moved. The game rules
+// go first, then each system takes what it cares about from the same events.
+fn dispatch_events(app: &mut App, state: &mut State) {
+    // Handlers may raise follow-up events, those are handled in the same frame.
+    loop {
+        let events = state.events.drain();
+        if events.is_empty() {
+            break;
         }
 
-        if state.monster.pos.x < -600. {
-            state.death_scene.show_text = true;
+        for event in &events {
+            handle_event(state, event);
+            state.sound_system.on_event(event, app);
+            state.minimap.on_event(event);
+            notify(state, event);
+            save::autosave(state, event);
         }
     }
 }
 
+fn handle_event(state: &mut State, event: &Event) {
+    match *event {
+        Event::FriendFound { id, .. } => {
+            if let Some(Entity {
+                ai: Some(Ai::Survivor { condition, air, .. }),
+                ..
+            }) = state.entities.get_mut(id)
+            {
+                *air = state.oxygen.diver_start(condition.air_start());
+            }
+            let key = format!("survivor.{}", state.entities.survivor_index(id));
+            if state.profile.claim(&key, RESCUE_REWARD) {
+                state.events.emit(Event::CreditsEarned {
+                    amount: RESCUE_REWARD,
+                    reason: "Rescue bonus",
+                });
+            }
+        }
+        Event::LogCollected { index } => {
+            if let Some(log) = state.logs.get(index) {
+                state.profile.collect_log(&log.key);
+            }
+        }
+        Event::Noise { pos, loudness } => {
+            state
+                .entities
+                .hear(pos, loudness, state.noise.lure, &mut state.events);
+        }
+        Event::Blast { pos, radius } => {
+            let breakable = &state.breakable;
+            state
+                .terrain
+                .carve_circle(pos, radius, |m| breakable.contains(&m));
+            state.blasts.push((pos, radius));
+            state.events.emit(Event::Noise {
+                pos,
+                loudness: state.noise.torpedo,
+            });
+        }
+        Event::Rockfall { pos, radius } => {
+            let player = state.player.pos + 16.;
+            state
+                .terrain
+                .fill_circle(pos, radius, Material::Rubble, player);
+            state.events.emit(Event::Noise {
+                pos,
+                loudness: state.noise.rockfall,
+            });
+
+            if player.distance(pos) < radius + 16. {
+                let amount = state.hull.rockfall();
+                state.events.emit(Event::PlayerDamaged {
+                    amount,
+                    source: DamageSource::Rockfall,
+                });
+            }
+        }
+        Event::SceneChanged { to: Scene::End, .. } => {
+            let (_, survivors) = state.entities.rescues();
+            let run = RunSummary {
+                rescued: state.entities.rescued(),
+                survivors,
+                seconds: state.run_time,
+                beacons_used: state.player.stats.beacons - state.num_of_beacons,
+                cells_used: state.power_cells.iter().filter(|c| c.taken).count(),
+            };
+            state.death_scene.score = Some(Score::new(&run));
+        }
+        Event::MonsterAwakened { .. } => set_scene(state, Scene::End),
+        _ => {}
+    }
+}
+
+// Messages and menus that follow what happened.
+fn notify(state: &mut State, event: &Event) {
+    let text = match *event {
+        Event::FriendFound { .. } => {
+            let (rescued, survivors) = state.entities.rescues();
+            format!("Survivor found ({rescued}/{survivors} rescued)")
+        }
+        Event::CreditsEarned { amount, reason } => format!("{reason}: {amount} credits"),
+        Event::LogCollected { index } => {
+            let Some(log) = state.logs.get(index) else {
+                return;
+            };
+            state.menus.refresh_journal(&state.profile, &state.logs);
+            format!("Log found: {}", log.heading())
+        }
+        _ => return,
+    };
+    state
+        .messages
+        .push(Message::new(&text, None, Priority::Low, 150.));
+}
+
+pub(crate) fn set_scene(state: &mut State, scene: Scene) {
+    if state.scene != scene {
+        let from = state.scene;
+        state.scene = scene;
+        state.events.emit(Event::SceneChanged { from, to: scene });
+    }
+}
+
 fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
     let mut player_draw = state.player_world_rtex.create_draw();
     player_draw.clear(Color::TRANSPARENT);
@@ -312,23 +658,31 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
     state.camera.apply(&mut player_draw);
 
     // player_draw.image(&state.cave_texture);
-    state.monster.render(&state.player.pos, &mut player_draw);
-
-    for beacon in &state.beacons {
-        if beacon.visible {
-            player_draw
-                .circle(10.)
-                .position(beacon.pos.x + 20., beacon.pos.y + 20.)
-                .stroke_color(Color::from_rgba(0.8, 0.0, 0.8, 1.0))
-                .stroke(4.);
-        }
-    }
+    state.entities.render(&mut player_draw);
 
     if state.scene != Scene::Start {
         state.player.render(&mut player_draw);
     }
 
-    state.friend.render(&state.player.pos, &mut player_draw);
+    for pocket in &state.air_pockets {
+        player_draw
+            .circle(pocket.radius)
+            .position(pocket.pos.x, pocket.pos.y)
+            .stroke_color(Color::from_rgba(0.6, 0.8, 1., 0.15))
+            .stroke(1.);
+    }
+
+    let pulse = (app.timer.elapsed_f32() * 3.).sin() * 0.25 + 0.5;
+    for cell in state.power_cells.iter().filter(|c| !c.taken) {
+        player_draw
+            .rect((cell.pos.x - 3., cell.pos.y - 3.), (6., 6.))
+            .color(Color::from_rgba(1., 0.85, 0.2, pulse));
+    }
+    for item in state.salvage.iter().filter(|s| !s.taken) {
+        player_draw
+            .rect((item.pos.x - 3., item.pos.y - 3.), (6., 6.))
+            .color(Color::from_rgba(0.4, 1., 0.6, pulse));
+    }
 
     for echo in &state.echoes {
         if echo.pos.x > state.camera.pos.x - RENDER_WIDTH
@@ -336,9 +690,18 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
             && echo.pos.y > state.camera.pos.y - RENDER_HEIGHT
             && echo.pos.y < state.camera.pos.y + RENDER_HEIGHT
         {
-            player_draw
-                .rect((echo.pos.x, echo.pos.y), (5., 5.))
-                .fill_color(echo.hit_color);
+            if state.settings.high_contrast {
+                // Bigger dots that stay bright for longer while fading out.
+                let mut color = echo.hit_color;
+                color.a = color.a.sqrt();
+                player_draw
+                    .rect((echo.pos.x - 1.5, echo.pos.y - 1.5), (8., 8.))
+                    .fill_color(color);
+            } else {
+                player_draw
+                    .rect((echo.pos.x, echo.pos.y), (5., 5.))
+                    .fill_color(echo.hit_color);
+            }
         }
     }
 
@@ -351,87 +714,88 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
     draw.image(&state.player_world_rtex.texture())
         .size(app.window().width() as f32, app.window().height() as f32);
 
-    // if state.scene == Scene::Game {
-    //     draw.text(
-    //         &state.font,
-    //         &format!("Beacons left: {}", state.num_of_beacons),
-    //     )
-    //     .size(40.)
-    //     .color(Color::WHITE);
-    // }
-    if state.show_found_text && state.found_text_timer > 0. {
-        draw.text(
+    if state.scene == Scene::Game {
+        let info = HudInfo {
+            heading: state.player.dir,
+            echo_ready: state.player.echo_ready(),
+            beacons_left: state.num_of_beacons,
+            max_beacons: state.player.stats.beacons,
+            torpedoes_left: state.num_of_torpedoes,
+            max_torpedoes: state.player.stats.torpedoes,
+            y: state.player.pos.y,
+            speed: state.player.speed(),
+            hull: state.hull.fraction(),
+            power: state.battery.fraction(),
+            oxygen: state.oxygen.pilot_fraction(),
+            friend_oxygen: state
+                .entities
+                .lowest_air()
+                .map(|air| state.oxygen.fraction(air)),
+            rescues: state.entities.rescues(),
+        };
+
+        state.hud.render(
+            &info,
             &state.font,
-            "Thank god you found me.. Please lead me back..",
-        )
-        .size(40.)
-        .color(Color::WHITE)
-        .h_align_center()
-        .v_align_middle()
-        .position(
-            app.window().width() as f32 / 2.,
-            app.window().height() as f32 / 1.1,
+            app.window().width() as f32,
+            app.window().height() as f32,
+            &mut draw,
         );
 
-        state.found_text_timer -= app.timer.delta_f32() * 60.;
-    }
+        let mut markers: Vec<MapMarker> = state
+            .entities
+            .of_kind(Kind::Beacon)
+            .map(|beacon| MapMarker {
+                pos: beacon.center(),
+                color: if beacon.is_visible() {
+                    Color::from_rgba(0.8, 0.0, 0.8, 1.0)
+                } else {
+                    Color::from_rgba(0.8, 0.0, 0.8, 0.4)
+                },
+            })
+            .collect();
+
+        for survivor in state.entities.of_kind(Kind::Survivor) {
+            if survivor.is_visible() {
+                markers.push(MapMarker {
+                    pos: survivor.center(),
+                    color: Color::WHITE,
+                });
+            }
+        }
 
-    if state.show_hint && state.show_hint_timer > 0. {
-        draw.text(
-            &state.font,
-            "There's some white debris left.. It must be this way",
-        )
-        .size(40.)
-        .color(Color::WHITE)
-        .h_align_center()
-        .v_align_middle()
-        .position(
-            app.window().width() as f32 / 2.,
-            app.window().height() as f32 / 1.1,
+        state.minimap.render(
+            gfx,
+            &mut draw,
+            state.player.pos + 16.,
+            state.player.dir,
+            &markers,
+            app.window().width() as f32,
+            app.window().height() as f32,
         );
-
-        state.show_hint_timer -= app.timer.delta_f32() * 60.;
     }
 
-    if state.scene == Scene::Start {
+    if state.hull.flash > 0. && state.scene != Scene::Start {
         draw.rect(
             (0., 0.),
             (app.window().width() as f32, app.window().height() as f32),
         )
-        .color(Color::from_bytes(0, 0, 0, 100));
+        .color(Color::from_rgba(1., 0., 0., state.hull.flash * 0.35));
+    }
 
-        let c = if app.mouse.x > app.window().width() as f32 / 4. - 120.
-            && app.mouse.x < app.window().width() as f32 / 4. + 120.
-            && app.mouse.y > app.window().height() as f32 / 2. - 30.
-            && app.mouse.y < app.window().height() as f32 / 2. + 30.
-        {
-            if app.mouse.left_was_pressed() {
-                state.music_start = true;
-                state.scene = Scene::Game;
-            }
-            Color::from_rgba(1., 1., 1., 0.25)
-        } else {
-            Color::TRANSPARENT
-        };
+    state.messages.render(
+        &state.font,
+        app.window().width() as f32,
+        app.window().height() as f32,
+        &mut draw,
+    );
 
+    if state.scene == Scene::Start {
         draw.rect(
-            (
-                app.window().width() as f32 / 4. - 120.,
-                app.window().height() as f32 / 2. - 30.,
-            ),
-            (240., 60.),
+            (0., 0.),
+            (app.window().width() as f32, app.window().height() as f32),
         )
-        .color(c);
-
-        draw.text(&state.font, "START")
-            .size(40.)
-            .color(Color::WHITE)
-            .h_align_center()
-            .v_align_middle()
-            .position(
-                app.window().width() as f32 / 4.,
-                app.window().height() as f32 / 2.,
-            );
+        .color(Color::from_bytes(0, 0, 0, 100));
 
         draw.text(&state.font, "Blind depths")
             .size(70.)
@@ -443,7 +807,7 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
                 app.window().height() as f32 / 10.,
             );
 
-        draw.text(&state.font, "STORY:\nYou're a submarine pilot in one of the deepest parts of the ocean and your only form of navigation is echoes you send that reveal the details of the cave walls. Your colleague got lost in one of the most complex deep ocean cave systems. Countless have already gone missing in that cave.\nRumors say that some kind of creature lives there..\n\nFind him and bring him back.\n\n\n\n\nControls:\nUse A and D keys to turn\nUse the W key to accelerate\nUse Space to send an echo\nUse the B key to place beacons (You only have 3)\n*Beacons are useful for navigation and marking areas")
+        draw.text(&state.font, &format!("STORY:\nYou're a submarine pilot in one of the deepest parts of the ocean and your only form of navigation is echoes you send that reveal the details of the cave walls. Your colleague got lost in one of the most complex deep ocean cave systems. Countless have already gone missing in that cave.\nRumors say that some kind of creature lives there..\n\nFind him and bring him back.\n\n\n\n\nControls:\n{}", controls_text(&state.settings, &state.profile.stats())))
             .size(22.)
             .color(Color::WHITE)
             .h_align_left()
@@ -463,7 +827,7 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
         .color(Color::from_rgba(0., 0., 0., state.death_scene.fade));
 
         if state.death_scene.show_text {
-            draw.text(&state.font, "The end...")
+            draw.text(&state.font, &state.death_scene.text)
                 .size(40.)
                 .color(Color::WHITE)
                 .h_align_center()
@@ -472,9 +836,40 @@ fn draw(app: &mut App, gfx: &mut Graphics, state: &mut State) {
                     app.window().width() as f32 / 2.,
                     app.window().height() as f32 / 2.,
                 );
+
+            if state.checkpoint.is_some() {
+                draw.text(&state.font, "Press Enter to return to the last checkpoint")
+                    .size(24.)
+                    .color(Color::from_rgba(0.8, 0.0, 0.8, 1.0))
+                    .h_align_center()
+                    .v_align_middle()
+                    .position(
+                        app.window().width() as f32 / 2.,
+                        app.window().height() as f32 / 2. + 60.,
+                    );
+            }
+
+            if let Some(score) = &state.death_scene.score {
+                draw.text(&state.font, &score.text())
+                    .size(24.)
+                    .color(Color::WHITE)
+                    .h_align_center()
+                    .v_align_top()
+                    .position(
+                        app.window().width() as f32 / 2.,
+                        app.window().height() as f32 / 2. + 120.,
+                    );
+            }
         }
     }
 
+    state.menus.render(
+        &state.font,
+        app.window().width() as f32,
+        app.window().height() as f32,
+        &mut draw,
+    );
+
     gfx.render(&draw);
 }
 
@@ -490,32 +885,313 @@ pub fn load_bytes(texture: &Texture, gfx: &mut Graphics) -> Vec<u8> {
     bytes
 }
 
-pub fn get_bg_color(bytes: &Vec<u8>, x: f32, y: f32) -> Color {
-    let xi = x.floor() as usize;
-    let yi = y.floor() as usize;
+// Hands the parts of the cave that changed since the last frame to everything that
+// keeps its own copy of it.
+fn sync_terrain(state: &mut State) {
+    for region in state.terrain.take_dirty() {
+        state.minimap.refresh(&state.terrain, region);
+        state.field.update(&state.terrain, region);
+    }
+}
+
+// Rebuilds the cave from the rockfalls and blasts of the run after a save is loaded.
+pub(crate) fn rebuild_terrain(state: &mut State) {
+    state.terrain.reset();
 
-    if xi >= 2017 || yi >= 2216 {
-        return Color::new(0.0, 0.0, 0.0, 0.0); // Transparent or error default
+    let spare = state.player.pos + 16.;
+    for entity in state.entities.of_kind(Kind::Rockfall) {
+        if let Some(Hazard::Rockfall {
+            radius,
+            fallen: true,
+        }) = entity.hazard
+        {
+            state
+                .terrain
+                .fill_circle(entity.center(), radius, Material::Rubble, spare);
+        }
     }
+    for &(pos, radius) in &state.blasts {
+        state
+            .terrain
+            .carve_circle(pos, radius, |m| state.breakable.contains(&m));
+    }
+}
 
-    let index = (yi * 2017 + xi) * 4;
+fn load_game(state: &mut State, slot: Slot) {
+    match save::read(slot) {
+        Ok(data) => {
+            state.player.stats = state.profile.stats();
+            respawn(state, &data);
+            state.checkpoint = Some(data);
+        }
+        Err(e) => log::warn!("Could not load {slot:?}: {e}"),
+    }
+}
+
+fn respawn(state: &mut State, data: &SaveData) {
+    data.apply(state);
+    set_scene(state, Scene::Game);
+    state.death_scene = DeathScene::new();
+    state.music_start = true;
+    state.sound_system.mixer.set_fade(0.);
+    state.sound_system.mixer.fade_to(1., 180.);
+}
 
-    let r = bytes[index] as f32 / 255.0;
-    let g = bytes[index + 1] as f32 / 255.0;
-    let b = bytes[index + 2] as f32 / 255.0;
-    let a = bytes[index + 3] as f32 / 255.0;
+fn update_hull(app: &mut App, state: &mut State) {
+    state.hull.update(app.timer.delta_f32());
+
+    let listener = state.sound_system.listener;
+    if let Some(impact) = &state.player.impact {
+        let amount = state.hull.impact(impact.speed, impact.material);
+        if amount > 0. {
+            state.events.emit(Event::PlayerDamaged {
+                amount,
+                source: DamageSource::Impact,
+            });
+            state.events.emit(Event::Noise {
+                pos: listener,
+                loudness: impact.speed * state.noise.impact,
+            });
+        }
+    }
+
+    if state.entities.is_hot(listener) {
+        let amount = state.hull.tuning.vent_damage * app.timer.delta_f32();
+        state.hull.vent_contact(app.timer.delta_f32());
+        state.events.emit(Event::PlayerDamaged {
+            amount,
+            source: DamageSource::Vent,
+        });
+    }
+
+    if state.hull.fraction() < 0.25 {
+        state.sound_system.play_at(Cue::Alarm, listener, app);
+    }
+
+    if state.hull.is_broken() {
+        game_over(state, "Your hull gave way...");
+    }
+}
+
+// Waking the creature ends the run, so it can only reach the sub during the chase.
+fn update_chase(app: &mut App, state: &mut State) {
+    state.hull.update(app.timer.delta_f32());
+    if state.hull.is_broken() {
+        return;
+    }
+
+    let listener = state.sound_system.listener;
+    let radius = state.hull.tuning.monster_radius;
+    if state
+        .entities
+        .of_kind(Kind::Creature)
+        .any(|c| c.is_awake() && listener.distance(c.center()) < radius)
+    {
+        let amount = state.hull.tuning.monster_damage * app.timer.delta_f32();
+        state.hull.monster_contact(app.timer.delta_f32());
+        state.events.emit(Event::PlayerDamaged {
+            amount,
+            source: DamageSource::Monster,
+        });
+
+        if state.hull.is_broken() {
+            state.death_scene.text = "The creature tore through your hull...".to_string();
+        }
+    }
+}
+
+fn update_battery(app: &mut App, state: &mut State) {
+    state
+        .battery
+        .update(state.player.throttle, app.timer.delta_f32());
+
+    let center = state.player.pos + 16.;
+    for cell in &mut state.power_cells {
+        if !cell.taken && center.distance(cell.pos) < 24. {
+            cell.taken = true;
+            state.battery.add(cell.amount);
+            state.sound_system.play_at(Cue::Beacon, cell.pos, app);
+        }
+    }
+
+    for (i, item) in state.salvage.iter_mut().enumerate() {
+        if !item.taken && center.distance(item.pos) < 24. {
+            item.taken = true;
+            state.sound_system.play_at(Cue::Beacon, item.pos, app);
+
+            let amount = item.amount as u32;
+            if state.profile.claim(&format!("salvage.{i}"), amount) {
+                state.events.emit(Event::CreditsEarned {
+                    amount,
+                    reason: "Salvage",
+                });
+            }
+        }
+    }
+}
+
+fn update_oxygen(app: &mut App, state: &mut State) {
+    let in_air = |pos: Vec2| {
+        state
+            .air_pockets
+            .iter()
+            .any(|pocket| pos.distance(pocket.pos) < pocket.radius)
+    };
+    let dt = app.timer.delta_f32();
+    let pilot_out = state.oxygen.update(in_air(state.player.pos + 16.), dt);
+
+    let mut diver_out = false;
+    for entity in &mut state.entities.list {
+        let center = entity.center();
+        if let Some(Ai::Survivor {
+            found: true,
+            condition,
+            air,
+        }) = &mut entity.ai
+        {
+            diver_out |= state
+                .oxygen
+                .breathe(air, in_air(center), condition.air_drain(), dt);
+        }
+    }
+
+    let lowest = state.entities.lowest_air();
+    if state
+        .oxygen
+        .is_low(lowest.map(|air| state.oxygen.fraction(air)))
+    {
+        let listener = state.sound_system.listener;
+        state.sound_system.play_at(Cue::Alarm, listener, app);
+    }
+
+    if pilot_out {
+        game_over(state, "You ran out of air...");
+    } else if diver_out {
+        game_over(state, "A diver you were leading ran out of air...");
+    }
+}
+
+fn game_over(state: &mut State, text: &str) {
+    set_scene(state, Scene::End);
+    state.death_scene = DeathScene::new();
+    state.death_scene.text = text.to_string();
+    state.sound_system.mixer.fade_to(0., 300.);
+}
+
+fn run_triggers(app: &mut App, state: &mut State) {
+    let ctx = TriggerContext {
+        player_pos: state.player.pos,
+        friend_found: state.entities.any_found(),
+        items: &state.items,
+    };
+    let actions: Vec<TriggerAction> = state
+        .triggers
+        .iter_mut()
+        .filter_map(|t| t.check(&ctx).cloned())
+        .collect();
+
+    for action in actions {
+        match action {
+            TriggerAction::Message(message) => state.messages.push(message),
+            TriggerAction::Cue(cue) => state.sound_system.play_at(cue, state.player.pos + 16., app),
+            TriggerAction::ActivateMonster => state.entities.wake_creatures(&mut state.events),
+            TriggerAction::SpawnBeacon(pos, freq) => {
+                state.entities.spawn(Entity::beacon(pos, true, freq));
+            }
+            TriggerAction::ChangeScene(Scene::End, text) if !text.is_empty() => {
+                game_over(state, &text)
+            }
+            TriggerAction::ChangeScene(scene, _) => set_scene(state, scene),
+            TriggerAction::Give(item) => {
+                if !state.items.contains(&item) {
+                    state.items.push(item);
+                }
+            }
+        }
+    }
+}
+
+fn reach_checkpoints(state: &mut State) {
+    let Some(i) = state
+        .checkpoints
+        .iter()
+        .position(|c| !c.reached && state.player.pos.distance(c.pos) < c.radius)
+    else {
+        return;
+    };
+
+    state.checkpoints[i].reached = true;
+    let data = SaveData::capture(state);
+    save::write(Slot::Auto, &data);
+    state.checkpoint = Some(data);
+
+    let text = format!("Checkpoint reached: {}", state.checkpoints[i].name);
+    state
+        .messages
+        .push(Message::new(&text, None, Priority::Low, 150.));
+}
+
+fn controls_text(settings: &Settings, stats: &SubStats) -> String {
+    let keys = |action: Action| input::binding_names(&settings.bindings[action as usize]);
+
+    format!(
+        "Use {} and {} to turn\nUse {} to accelerate and {} to reverse, {} and {} to strafe\nUse {} to send an echo\nUse {} to place beacons (You only have {})\n*Beacons are useful for navigation and marking areas\nUse {} to fire a torpedo through cracked rock (You have {})\nUse H to toggle the HUD and M to open the map (Q/E zoom, arrows pan)\nUse {} to pause, controls can be changed in the settings",
+        keys(Action::TurnLeft),
+        keys(Action::TurnRight),
+        keys(Action::Thrust),
+        keys(Action::Reverse),
+        keys(Action::StrafeLeft),
+        keys(Action::StrafeRight),
+        keys(Action::Ping),
+        keys(Action::DropBeacon),
+        stats.beacons,
+        keys(Action::Torpedo),
+        stats.torpedoes,
+        keys(Action::Pause),
+    )
+}
+
+fn apply_settings(app: &mut App, state: &mut State) {
+    let settings = &state.settings;
+
+    for bus in Bus::ALL {
+        state
+            .sound_system
+            .mixer
+            .set_volume(bus, settings.volumes[bus as usize]);
+    }
+
+    let window = app.window();
+    if window.is_fullscreen() != settings.fullscreen {
+        window.set_fullscreen(settings.fullscreen);
+    }
+    if !settings.fullscreen && window.size() != (settings.resolution.0, settings.resolution.1) {
+        window.set_size(settings.resolution.0, settings.resolution.1);
+    }
 
-    Color::new(r, g, b, a)
+    state.messages.text_scale = settings.text_scale;
+    state.hud.text_scale = settings.text_scale;
 }
 
-pub fn send_echo(echoes: &mut Vec<Echo>, pos: &Vec2, dir: f32, color: Color) {
-    for i in 0..=60 {
-        let echo = Echo::new(
+pub fn send_echo(
+    echoes: &mut Vec<Echo>,
+    pos: &Vec2,
+    dir: f32,
+    color: Color,
+    audible: bool,
+    range: f32,
+    rays: usize,
+) {
+    let spacing = 360. / rays.saturating_sub(1).max(1) as f32;
+    for i in 0..rays {
+        let mut echo = Echo::new(
             pos.x + 16.,
             pos.y + 16.,
-            (i as f32 * 6.).to_radians() - dir,
+            (i as f32 * spacing).to_radians() - dir,
             color,
         );
+        echo.audible = audible;
+        echo.range = range;
 
         echoes.push(echo);
     }
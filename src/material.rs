@@ -0,0 +1,92 @@
+use notan::app::Color;
+
+// What the cave is made of, read from the colors of the cave image.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Material {
+    Water,
+    Rock,
+    Coral,
+    Algae,
+    Boundary,
+    Debris,
+    // Rock that came down while playing.
+    Rubble,
+    // Cracked rock plugging old passages.
+    Shale,
+}
+
+impl Material {
+    pub const ALL: [Material; 8] = [
+        Material::Water,
+        Material::Rock,
+        Material::Coral,
+        Material::Algae,
+        Material::Boundary,
+        Material::Debris,
+        Material::Rubble,
+        Material::Shale,
+    ];
+
+    // Name used by the level's material palette.
+    pub fn key(self) -> &'static str {
+        match self {
+            Material::Water => "water",
+            Material::Rock => "rock",
+            Material::Coral => "coral",
+            Material::Algae => "algae",
+            Material::Boundary => "boundary",
+            Material::Debris => "debris",
+            Material::Rubble => "rubble",
+            Material::Shale => "shale",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Material> {
+        Material::ALL.into_iter().find(|m| m.key() == key)
+    }
+
+    pub fn from_color(color: Color) -> Self {
+        match color.rgba_u8() {
+            [0, 0, 0, _] => Material::Water,
+            [0x45, 0x28, 0x3c, _] => Material::Coral,
+            [0x20, 0x34, 0x27, _] => Material::Algae,
+            [0xfb, 0, 0, _] => Material::Boundary,
+            [0xff, 0xff, 0xff, _] => Material::Debris,
+            [0x3a, 0x3a, 0x48, _] => Material::Rubble,
+            [0x4a, 0x3f, 0x5c, _] => Material::Shale,
+            _ => Material::Rock,
+        }
+    }
+
+    // What gets painted into the cave when it changes at runtime.
+    pub fn color(self) -> [u8; 3] {
+        match self {
+            Material::Water => [0, 0, 0],
+            Material::Rock => [0x22, 0x20, 0x34],
+            Material::Coral => [0x45, 0x28, 0x3c],
+            Material::Algae => [0x20, 0x34, 0x27],
+            Material::Boundary => [0xfb, 0, 0],
+            Material::Debris => [0xff, 0xff, 0xff],
+            Material::Rubble => [0x3a, 0x3a, 0x48],
+            Material::Shale => [0x4a, 0x3f, 0x5c],
+        }
+    }
+
+    pub fn is_hazard(self) -> bool {
+        self == Material::Coral
+    }
+
+    // How hard an impact against this material hits the hull.
+    pub fn hardness(self) -> f32 {
+        match self {
+            Material::Water => 0.,
+            Material::Algae => 0.5,
+            Material::Debris => 0.75,
+            Material::Rock
+            | Material::Coral
+            | Material::Boundary
+            | Material::Rubble
+            | Material::Shale => 1.,
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use notan::{
+    app::Color,
+    draw::{Draw, DrawShapes, DrawTextSection, Font},
+    math::Vec2,
+    prelude::KeyCode,
+};
+
+use crate::{
+    input::{self, Action},
+    lore::LogEntry,
+    save::{self, SLOTS, Slot},
+    settings::{RESOLUTIONS, Settings, TEXT_SCALES},
+    sound::Bus,
+    ui::{Menu, UiEvent, UiInput, Widget, WidgetKind},
+    upgrades::{MAX_LEVEL, Profile, Upgrade},
+};
+
+const SLOT_IDS: [&str; SLOTS] = ["slot1", "slot2", "slot3"];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Screen {
+    Start,
+    Pause,
+    Settings,
+    Controls,
+    Save,
+    Load,
+    Upgrades,
+    Journal,
+}
+
+pub enum MenuAction {
+    Start,
+    Resume,
+    Quit,
+    Save(Slot),
+    Load(Slot),
+    Buy(Upgrade),
+    SettingsChanged,
+    SettingsClosed,
+}
+
+pub struct Menus {
+    pub screen: Option<Screen>,
+    previous: Vec<Screen>,
+    start: Menu,
+    pause: Menu,
+    settings: Menu,
+    controls: Menu,
+    save: Menu,
+    load: Menu,
+    upgrades: Menu,
+    journal: Menu,
+    // Text of each found log, in the order of the journal's entry list.
+    journal_texts: Vec<String>,
+    journal_total: usize,
+    credits: u32,
+    // The action waiting for a key press in the controls menu.
+    capturing: Option<Action>,
+}
+
+impl Menus {
+    pub fn new(settings: &Settings, profile: &Profile) -> Self {
+        let resolutions = RESOLUTIONS
+            .iter()
+            .map(|(w, h)| format!("{w}x{h}"))
+            .collect();
+        let text_scales = TEXT_SCALES
+            .iter()
+            .map(|s| format!("{:.0}%", s * 100.))
+            .collect();
+
+        let mut widgets = vec![
+            Widget::list(
+                "resolution",
+                "RESOLUTION",
+                resolutions,
+                settings.resolution_index(),
+            ),
+            Widget::toggle("fullscreen", "FULLSCREEN", settings.fullscreen),
+            Widget::toggle("vsync", "VSYNC (ON RESTART)", settings.vsync),
+        ];
+        for bus in Bus::ALL {
+            let label = format!("{} VOLUME", bus.key().to_uppercase());
+            widgets.push(Widget::slider(
+                bus.key(),
+                &label,
+                settings.volumes[bus as usize],
+            ));
+        }
+        widgets.extend([
+            Widget::list(
+                "text_scale",
+                "TEXT SIZE",
+                text_scales,
+                settings.text_scale_index(),
+            ),
+            Widget::toggle("high_contrast", "HIGH CONTRAST", settings.high_contrast),
+            Widget::button("controls", "CONTROLS"),
+            Widget::button("back", "BACK"),
+        ]);
+
+        let mut controls: Vec<Widget> = Action::ALL
+            .iter()
+            .map(|action| {
+                let keys = input::binding_names(&settings.bindings[*action as usize]);
+                Widget::binding(action.key(), action.label(), &keys)
+            })
+            .collect();
+        controls.push(Widget::button("reset", "RESET TO DEFAULTS"));
+        controls.push(Widget::button("back", "BACK"));
+
+        let slots = |autosave: bool| {
+            let mut widgets = vec![];
+            if autosave {
+                widgets.push(Widget::binding("auto", "AUTOSAVE", ""));
+            }
+            for (i, id) in SLOT_IDS.iter().enumerate() {
+                widgets.push(Widget::binding(id, &format!("SLOT {}", i + 1), ""));
+            }
+            widgets.push(Widget::button("back", "BACK"));
+            Menu::new(widgets)
+        };
+
+        let mut menus = Self {
+            screen: Some(Screen::Start),
+            previous: vec![],
+            start: Menu::new(vec![
+                Widget::button("start", "START"),
+                Widget::button("continue", "CONTINUE"),
+                Widget::button("load", "LOAD"),
+                Widget::button("upgrades", "UPGRADES"),
+                Widget::button("journal", "JOURNAL"),
+                Widget::button("settings", "SETTINGS"),
+            ]),
+            pause: Menu::new(vec![
+                Widget::button("resume", "RESUME"),
+                Widget::button("save", "SAVE"),
+                Widget::button("load", "LOAD"),
+                Widget::button("journal", "JOURNAL"),
+                Widget::button("settings", "SETTINGS"),
+                Widget::button("quit", "QUIT"),
+            ]),
+            settings: Menu::new(widgets),
+            controls: Menu::new(controls),
+            save: slots(false),
+            load: slots(true),
+            upgrades: Menu::new(
+                Upgrade::ALL
+                    .iter()
+                    .map(|u| Widget::binding(u.key(), u.label(), ""))
+                    .chain([Widget::button("back", "BACK")])
+                    .collect(),
+            ),
+            journal: Menu::new(vec![]),
+            journal_texts: vec![],
+            journal_total: 0,
+            credits: 0,
+            capturing: None,
+        };
+        menus.refresh_upgrades(profile);
+        menus.refresh_journal(profile, &[]);
+        menus
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.screen.is_some()
+    }
+
+    pub fn open(&mut self, screen: Screen) {
+        if matches!(screen, Screen::Save | Screen::Load) {
+            self.refresh_slots();
+        }
+
+        if let Some(current) = self.screen {
+            self.previous.push(current);
+        }
+        self.screen = Some(screen);
+    }
+
+    fn close(&mut self) {
+        self.screen = self.previous.pop();
+    }
+
+    fn close_all(&mut self) {
+        self.screen = None;
+        self.previous.clear();
+    }
+
+    pub fn refresh_upgrades(&mut self, profile: &Profile) {
+        self.credits = profile.credits;
+        for upgrade in Upgrade::ALL {
+            let level = profile.levels[upgrade as usize];
+            let value = match Upgrade::cost(level) {
+                Some(cost) => format!("LV {level}/{MAX_LEVEL}  {cost} CR"),
+                None => format!("LV {level}/{MAX_LEVEL}  MAX"),
+            };
+            if let Some(widget) = self.upgrades.get(upgrade.key()) {
+                widget.kind = WidgetKind::Binding(value);
+            }
+        }
+    }
+
+    pub fn refresh_journal(&mut self, profile: &Profile, logs: &[LogEntry]) {
+        let found: Vec<&LogEntry> = logs
+            .iter()
+            .filter(|log| profile.logs.contains(&log.key))
+            .collect();
+
+        let headings = if found.is_empty() {
+            vec!["NOTHING FOUND YET".to_string()]
+        } else {
+            found.iter().map(|log| log.heading()).collect()
+        };
+        self.journal = Menu::new(vec![
+            Widget::list("entry", "ENTRY", headings, 0),
+            Widget::button("back", "BACK"),
+        ]);
+        self.journal_texts = found.iter().map(|log| log.text.clone()).collect();
+        self.journal_total = logs.len();
+    }
+
+    fn journal_selected(&self) -> usize {
+        self.journal
+            .widgets
+            .iter()
+            .find_map(|w| match &w.kind {
+                WidgetKind::List(_, selected) if w.id == "entry" => Some(*selected),
+                _ => None,
+            })
+            .unwrap_or(0)
+    }
+
+    fn refresh_slots(&mut self) {
+        let slots = SLOT_IDS
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (*id, Slot::Manual(i)))
+            .chain([("auto", Slot::Auto)]);
+
+        for (id, slot) in slots {
+            let summary = save::summary(slot);
+            for menu in [&mut self.save, &mut self.load] {
+                if let Some(widget) = menu.get(id) {
+                    widget.kind = WidgetKind::Binding(summary.clone());
+                }
+            }
+        }
+    }
+
+    fn layout(&mut self, width: f32, height: f32) {
+        self.start.layout(
+            Vec2::new(width / 4. - 120., height / 2. - 80.),
+            Vec2::new(240., 60.),
+            10.,
+        );
+
+        let size = Vec2::new(600., 60.);
+        let pos = Vec2::new((width - size.x) / 2., height / 3.);
+        self.pause.layout(pos, size, 10.);
+
+        let size = Vec2::new(700., 42.);
+        let pos = Vec2::new((width - size.x) / 2., height / 6. + 60.);
+        self.settings.layout(pos, size, 6.);
+        // One row per action, packed tighter so they all fit at 720p.
+        self.controls.layout(pos, Vec2::new(size.x, 38.), 4.);
+
+        let pos = Vec2::new((width - size.x) / 2., height / 3.);
+        self.save.layout(pos, size, 6.);
+        self.load.layout(pos, size, 6.);
+        self.upgrades.layout(pos, size, 6.);
+        self.journal.layout(pos, size, 6.);
+    }
+
+    pub fn update(
+        &mut self,
+        input: &UiInput,
+        settings: &mut Settings,
+        width: f32,
+        height: f32,
+    ) -> Option<MenuAction> {
+        let screen = self.screen?;
+        self.layout(width, height);
+
+        match screen {
+            Screen::Start => match self.start.update(input)? {
+                UiEvent::Pressed("start") => {
+                    self.screen = None;
+                    Some(MenuAction::Start)
+                }
+                UiEvent::Pressed("continue") if save::exists(Slot::Auto) => {
+                    self.close_all();
+                    Some(MenuAction::Load(Slot::Auto))
+                }
+                UiEvent::Pressed("load") => {
+                    self.open(Screen::Load);
+                    None
+                }
+                UiEvent::Pressed("upgrades") => {
+                    self.open(Screen::Upgrades);
+                    None
+                }
+                UiEvent::Pressed("journal") => {
+                    self.open(Screen::Journal);
+                    None
+                }
+                UiEvent::Pressed("settings") => {
+                    self.open(Screen::Settings);
+                    None
+                }
+                _ => None,
+            },
+            Screen::Pause => match self.pause.update(input)? {
+                UiEvent::Pressed("resume") | UiEvent::Back => {
+                    self.screen = None;
+                    Some(MenuAction::Resume)
+                }
+                UiEvent::Pressed("save") => {
+                    self.open(Screen::Save);
+                    None
+                }
+                UiEvent::Pressed("load") => {
+                    self.open(Screen::Load);
+                    None
+                }
+                UiEvent::Pressed("journal") => {
+                    self.open(Screen::Journal);
+                    None
+                }
+                UiEvent::Pressed("settings") => {
+                    self.open(Screen::Settings);
+                    None
+                }
+                UiEvent::Pressed("quit") => Some(MenuAction::Quit),
+                _ => None,
+            },
+            Screen::Settings => {
+                match self.settings.update(input)? {
+                    UiEvent::List("resolution", i) => settings.resolution = RESOLUTIONS[i],
+                    UiEvent::Toggle("fullscreen", on) => settings.fullscreen = on,
+                    UiEvent::Toggle("vsync", on) => settings.vsync = on,
+                    UiEvent::Slider(id, value) => {
+                        let bus = Bus::ALL.into_iter().find(|bus| bus.key() == id)?;
+                        settings.volumes[bus as usize] = value;
+                    }
+                    UiEvent::List("text_scale", i) => settings.text_scale = TEXT_SCALES[i],
+                    UiEvent::Toggle("high_contrast", on) => settings.high_contrast = on,
+                    UiEvent::Pressed("controls") => {
+                        self.open(Screen::Controls);
+                        return None;
+                    }
+                    UiEvent::Pressed("back") | UiEvent::Back => {
+                        self.close();
+                        return Some(MenuAction::SettingsClosed);
+                    }
+                    _ => return None,
+                }
+
+                Some(MenuAction::SettingsChanged)
+            }
+            Screen::Controls => self.update_controls(input, settings),
+            Screen::Save => match self.save.update(input)? {
+                UiEvent::Pressed("back") | UiEvent::Back => {
+                    self.close();
+                    None
+                }
+                UiEvent::Pressed(id) => {
+                    let slot = slot_from_id(id)?;
+                    self.close();
+                    Some(MenuAction::Save(slot))
+                }
+                _ => None,
+            },
+            Screen::Upgrades => match self.upgrades.update(input)? {
+                UiEvent::Pressed("back") | UiEvent::Back => {
+                    self.close();
+                    None
+                }
+                UiEvent::Pressed(id) => Upgrade::from_key(id).map(MenuAction::Buy),
+                _ => None,
+            },
+            Screen::Journal => match self.journal.update(input)? {
+                UiEvent::Pressed("back") | UiEvent::Back => {
+                    self.close();
+                    None
+                }
+                _ => None,
+            },
+            Screen::Load => match self.load.update(input)? {
+                UiEvent::Pressed("back") | UiEvent::Back => {
+                    self.close();
+                    None
+                }
+                UiEvent::Pressed(id) => {
+                    let slot = slot_from_id(id).filter(|slot| save::exists(*slot))?;
+                    self.close_all();
+                    Some(MenuAction::Load(slot))
+                }
+                _ => None,
+            },
+        }
+    }
+
+    fn update_controls(&mut self, input: &UiInput, settings: &mut Settings) -> Option<MenuAction> {
+        if let Some(action) = self.capturing {
+            let key = input.key?;
+            self.capturing = None;
+            if key != KeyCode::Escape {
+                input::rebind(&mut settings.bindings, action, key);
+            }
+            self.refresh_controls(settings);
+            return Some(MenuAction::SettingsChanged);
+        }
+
+        match self.controls.update(input)? {
+            UiEvent::Pressed("reset") => {
+                settings.bindings = input::default_bindings();
+                self.refresh_controls(settings);
+                Some(MenuAction::SettingsChanged)
+            }
+            UiEvent::Pressed("back") | UiEvent::Back => {
+                self.close();
+                None
+            }
+            UiEvent::Pressed(id) => {
+                self.capturing = Action::from_key(id);
+                if let Some(widget) = self.controls.get(id) {
+                    widget.kind = WidgetKind::Binding("PRESS A KEY".to_string());
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn refresh_controls(&mut self, settings: &Settings) {
+        for action in Action::ALL {
+            let keys = input::binding_names(&settings.bindings[action as usize]);
+            if let Some(widget) = self.controls.get(action.key()) {
+                widget.kind = WidgetKind::Binding(keys);
+            }
+        }
+    }
+
+    pub fn render(&self, font: &Font, width: f32, height: f32, draw: &mut Draw) {
+        let Some(screen) = self.screen else {
+            return;
+        };
+
+        let (menu, title) = match screen {
+            Screen::Start => {
+                self.start.render(font, draw);
+                return;
+            }
+            Screen::Pause => (&self.pause, "Paused".to_string()),
+            Screen::Settings => (&self.settings, "Settings".to_string()),
+            Screen::Controls => (&self.controls, "Controls".to_string()),
+            Screen::Save => (&self.save, "Save game".to_string()),
+            Screen::Load => (&self.load, "Load game".to_string()),
+            Screen::Upgrades => (&self.upgrades, format!("Upgrades - {} cr", self.credits)),
+            Screen::Journal => (
+                &self.journal,
+                format!(
+                    "Journal - {}/{}",
+                    self.journal_texts.len(),
+                    self.journal_total
+                ),
+            ),
+        };
+
+        draw.rect((0., 0.), (width, height))
+            .color(Color::from_rgba(0., 0., 0., 0.8));
+
+        draw.text(font, &title)
+            .size(70.)
+            .color(Color::WHITE)
+            .h_align_center()
+            .v_align_middle()
+            .position(width / 2., height / 10.);
+
+        menu.render(font, draw);
+
+        if screen == Screen::Journal
+            && let Some(text) = self.journal_texts.get(self.journal_selected())
+        {
+            draw.text(font, text)
+                .size(28.)
+                .color(Color::WHITE)
+                .max_width(width * 0.6)
+                .h_align_center()
+                .position(width / 2., height / 3. + 140.);
+        }
+    }
+}
+
+fn slot_from_id(id: &str) -> Option<Slot> {
+    if id == "auto" {
+        return Some(Slot::Auto);
+    }
+    SLOT_IDS.iter().position(|s| *s == id).map(Slot::Manual)
+}
@@ -0,0 +1,132 @@
+use notan::{
+    app::Color,
+    draw::{Draw, DrawTextSection, Font},
+};
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Priority {
+    Low,
+    Normal,
+    High,
+}
+
+#[derive(Clone, Debug)]
+pub struct Message {
+    pub text: String,
+    pub speaker: Option<String>,
+    pub priority: Priority,
+    pub duration: f32,
+}
+
+impl Message {
+    pub fn new(text: &str, speaker: Option<&str>, priority: Priority, duration: f32) -> Self {
+        Self {
+            text: text.to_string(),
+            speaker: speaker.map(str::to_string),
+            priority,
+            duration,
+        }
+    }
+}
+
+// Shows one line at a time at the bottom of the screen. A more important line
+// interrupts the current one, which goes back to the front of the queue.
+pub struct MessageQueue {
+    queue: Vec<Message>,
+    current: Option<Message>,
+    timer: f32,
+    pub text_scale: f32,
+}
+
+impl MessageQueue {
+    pub fn new() -> Self {
+        Self {
+            queue: vec![],
+            current: None,
+            timer: 0.,
+            text_scale: 1.,
+        }
+    }
+
+    pub fn push(&mut self, message: Message) {
+        match &self.current {
+            None => {
+                self.show(message);
+                return;
+            }
+            Some(current) if message.priority > current.priority => {
+                let mut interrupted = self.current.take().unwrap();
+                interrupted.duration = self.timer;
+                self.queue.insert(0, interrupted);
+                self.show(message);
+                return;
+            }
+            _ => {}
+        }
+
+        let index = self
+            .queue
+            .iter()
+            .position(|m| m.priority < message.priority)
+            .unwrap_or(self.queue.len());
+        self.queue.insert(index, message);
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+        self.current = None;
+    }
+
+    pub fn current(&self) -> Option<&Message> {
+        self.current.as_ref()
+    }
+
+    fn show(&mut self, message: Message) {
+        self.timer = message.duration;
+        self.current = Some(message);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        if self.current.is_none() {
+            return;
+        }
+
+        self.timer -= dt * 60.;
+        if self.timer <= 0. {
+            self.current = None;
+            if !self.queue.is_empty() {
+                let next = self.queue.remove(0);
+                self.show(next);
+            }
+        }
+    }
+
+    pub fn render(&self, font: &Font, width: f32, height: f32, draw: &mut Draw) {
+        let Some(message) = &self.current else {
+            return;
+        };
+
+        if let Some(speaker) = &message.speaker {
+            draw.text(font, speaker)
+                .size(28. * self.text_scale)
+                .color(Color::from_rgba(0.8, 0.0, 0.8, 1.0))
+                .h_align_center()
+                .v_align_middle()
+                .position(width / 2., height / 1.1 - 40. * self.text_scale);
+        }
+
+        draw.text(font, &message.text)
+            .size(40. * self.text_scale)
+            .color(Color::WHITE)
+            .h_align_center()
+            .v_align_middle()
+            .max_width(width * 0.6)
+            .position(width / 2., height / 1.1);
+    }
+}
+
+impl Default for MessageQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,246 @@
+use notan::{
+    app::{App, Color, Graphics, Texture},
+    draw::{Draw, DrawImages, DrawShapes},
+    math::Vec2,
+    prelude::{KeyCode, TextureFilter},
+};
+
+use crate::{
+    events::Event,
+    terrain::{Region, Terrain},
+};
+
+// Every map cell covers this many cave pixels.
+const CELL: f32 = 4.;
+
+const MIN_VIEW: f32 = 300.;
+const PAN_SPEED: f32 = 8.;
+
+pub struct MapMarker {
+    pub pos: Vec2,
+    pub color: Color,
+}
+
+// Remembers every wall an echo has touched, so the map only ever shows what was heard.
+pub struct Minimap {
+    pub visible: bool,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    texture: Texture,
+    dirty: bool,
+    view: f32,
+    pan: Vec2,
+}
+
+impl Minimap {
+    pub fn new(gfx: &mut Graphics, world_width: f32, world_height: f32) -> Self {
+        let width = (world_width / CELL).ceil() as usize;
+        let height = (world_height / CELL).ceil() as usize;
+        let pixels = vec![0; width * height * 4];
+
+        Self {
+            visible: false,
+            width,
+            height,
+            texture: gfx
+                .create_texture()
+                .from_bytes(&pixels, width as u32, height as u32)
+                .with_filter(TextureFilter::Nearest, TextureFilter::Nearest)
+                .build()
+                .unwrap(),
+            pixels,
+            dirty: false,
+            view: 800.,
+            pan: Vec2::ZERO,
+        }
+    }
+
+    pub fn reveal(&mut self, pos: Vec2, color: Color) {
+        if pos.x < 0. || pos.y < 0. {
+            return;
+        }
+
+        let x = (pos.x / CELL) as usize;
+        let y = (pos.y / CELL) as usize;
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        let index = (y * self.width + x) * 4;
+        let [r, g, b, _] = color.rgba_u8();
+        self.pixels[index..index + 4].copy_from_slice(&[r, g, b, 255]);
+        self.dirty = true;
+    }
+
+    // Echoes off the cave mark the map. Entities move, so echoes off them don't.
+    pub fn on_event(&mut self, event: &Event) {
+        if let Event::EchoHit {
+            pos,
+            color,
+            entity: None,
+            ..
+        } = *event
+        {
+            self.reveal(pos, color);
+        }
+    }
+
+    // Brings revealed cells inside `region` of the cave up to date after it changed,
+    // walls that are gone disappear from the map. Cells never heard stay hidden.
+    pub fn refresh(&mut self, terrain: &Terrain, region: Region) {
+        let cell = CELL as usize;
+        for y in region.y0 / cell..region.y1.div_ceil(cell).min(self.height) {
+            for x in region.x0 / cell..region.x1.div_ceil(cell).min(self.width) {
+                let index = (y * self.width + x) * 4;
+                if self.pixels[index + 3] == 0 {
+                    continue;
+                }
+
+                let center = (Vec2::new(x as f32, y as f32) + 0.5) * CELL;
+                let color = terrain.color(center.x, center.y);
+                self.pixels[index..index + 4].copy_from_slice(&if color == Color::BLACK {
+                    [0; 4]
+                } else {
+                    color.rgba_u8()
+                });
+                self.dirty = true;
+            }
+        }
+    }
+
+    // Every revealed cell as (x, y, rgb), used by save files.
+    pub fn revealed(&self) -> Vec<(u32, u32, [u8; 3])> {
+        self.pixels
+            .chunks(4)
+            .enumerate()
+            .filter(|(_, p)| p[3] > 0)
+            .map(|(i, p)| {
+                let x = (i % self.width) as u32;
+                let y = (i / self.width) as u32;
+                (x, y, [p[0], p[1], p[2]])
+            })
+            .collect()
+    }
+
+    pub fn restore(&mut self, cells: &[(u32, u32, [u8; 3])]) {
+        self.pixels.fill(0);
+        for &(x, y, [r, g, b]) in cells {
+            let (x, y) = (x as usize, y as usize);
+            if x < self.width && y < self.height {
+                let index = (y * self.width + x) * 4;
+                self.pixels[index..index + 4].copy_from_slice(&[r, g, b, 255]);
+            }
+        }
+        self.dirty = true;
+    }
+
+    pub fn update(&mut self, app: &mut App) {
+        if app.keyboard.was_pressed(KeyCode::M) {
+            self.visible = !self.visible;
+        }
+
+        if !self.visible {
+            return;
+        }
+
+        let step = app.timer.delta_f32() * 60.;
+        let max_view = (self.width.min(self.height) as f32) * CELL;
+
+        if app.keyboard.is_down(KeyCode::Q) || app.mouse.wheel_delta.y < 0. {
+            self.view *= 1. + 0.02 * step;
+        }
+        if app.keyboard.is_down(KeyCode::E) || app.mouse.wheel_delta.y > 0. {
+            self.view /= 1. + 0.02 * step;
+        }
+        self.view = self.view.clamp(MIN_VIEW, max_view);
+
+        let speed = PAN_SPEED * step * self.view / 800.;
+        if app.keyboard.is_down(KeyCode::Left) {
+            self.pan.x -= speed;
+        }
+        if app.keyboard.is_down(KeyCode::Right) {
+            self.pan.x += speed;
+        }
+        if app.keyboard.is_down(KeyCode::Up) {
+            self.pan.y -= speed;
+        }
+        if app.keyboard.is_down(KeyCode::Down) {
+            self.pan.y += speed;
+        }
+        if app.keyboard.was_pressed(KeyCode::C) {
+            self.pan = Vec2::ZERO;
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &mut self,
+        gfx: &mut Graphics,
+        draw: &mut Draw,
+        player_pos: Vec2,
+        player_dir: f32,
+        markers: &[MapMarker],
+        width: f32,
+        height: f32,
+    ) {
+        if !self.visible {
+            return;
+        }
+
+        if self.dirty {
+            gfx.update_texture(&mut self.texture)
+                .with_data(&self.pixels)
+                .update()
+                .unwrap();
+            self.dirty = false;
+        }
+
+        let size = width.min(height) * 0.8;
+        let panel = Vec2::new(width - size, height - size) * 0.5;
+        let scale = size / self.view;
+
+        let world = Vec2::new(self.width as f32, self.height as f32) * CELL;
+        let origin = (player_pos + self.pan - self.view * 0.5).clamp(Vec2::ZERO, world - self.view);
+        self.pan = origin + self.view * 0.5 - player_pos;
+
+        draw.rect((panel.x, panel.y), (size, size))
+            .color(Color::from_rgba(0., 0., 0., 0.85));
+
+        draw.image(&self.texture)
+            .crop(
+                (origin.x / CELL, origin.y / CELL),
+                (self.view / CELL, self.view / CELL),
+            )
+            .position(panel.x, panel.y)
+            .size(size, size);
+
+        draw.rect((panel.x, panel.y), (size, size))
+            .stroke_color(Color::from_rgba(0.8, 0.0, 0.8, 1.0))
+            .stroke(3.);
+
+        let to_panel = |pos: Vec2| panel + (pos - origin) * scale;
+        let inside = |p: Vec2| {
+            p.x > panel.x && p.x < panel.x + size && p.y > panel.y && p.y < panel.y + size
+        };
+
+        for marker in markers {
+            let p = to_panel(marker.pos);
+            if inside(p) {
+                draw.circle(6.)
+                    .position(p.x, p.y)
+                    .stroke_color(marker.color)
+                    .stroke(3.);
+            }
+        }
+
+        let p = to_panel(player_pos);
+        if inside(p) {
+            let tip = p + Vec2::from_angle(player_dir.to_radians()) * 14.;
+            draw.circle(5.).position(p.x, p.y).color(Color::WHITE);
+            draw.line((p.x, p.y), (tip.x, tip.y))
+                .color(Color::WHITE)
+                .width(3.);
+        }
+    }
+}
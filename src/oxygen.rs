@@ -0,0 +1,117 @@
+use crate::tuning::Tuning;
+
+pub struct OxygenTuning {
+    pub max: f32,
+    pub pilot_drain: f32,
+    pub friend_drain: f32,
+    pub friend_start: f32,
+    pub refill: f32,
+    pub warning: f32,
+}
+
+impl OxygenTuning {
+    pub fn new(tuning: &Tuning) -> Self {
+        Self {
+            max: tuning.get("oxygen.max"),
+            pilot_drain: tuning.get("oxygen.pilot_drain"),
+            friend_drain: tuning.get("oxygen.friend_drain"),
+            friend_start: tuning.get("oxygen.friend_start"),
+            refill: tuning.get("oxygen.refill"),
+            warning: tuning.get("oxygen.warning"),
+        }
+    }
+}
+
+// Air left for the pilot. Found divers carry their own tank, see `breathe`.
+pub struct Oxygen {
+    pub pilot: f32,
+    pub tuning: OxygenTuning,
+}
+
+impl Oxygen {
+    pub fn new(tuning: OxygenTuning) -> Self {
+        Self {
+            pilot: tuning.max,
+            tuning,
+        }
+    }
+
+    // Returns true once the pilot is out of air.
+    pub fn update(&mut self, pilot_in_air: bool, dt: f32) -> bool {
+        let t = &self.tuning;
+
+        let pilot_rate = if pilot_in_air {
+            t.refill
+        } else {
+            -t.pilot_drain
+        };
+        self.pilot = (self.pilot + pilot_rate * dt).clamp(0., t.max);
+
+        self.pilot <= 0.
+    }
+
+    // Tank a diver starts with when found, scaled by their condition.
+    pub fn diver_start(&self, scale: f32) -> f32 {
+        self.tuning.friend_start * scale
+    }
+
+    // Same as `update` for a found diver's tank, `drain` scales how fast they use it.
+    pub fn breathe(&self, air: &mut f32, in_air: bool, drain: f32, dt: f32) -> bool {
+        let t = &self.tuning;
+
+        let rate = if in_air {
+            t.refill
+        } else {
+            -t.friend_drain * drain
+        };
+        *air = (*air + rate * dt).clamp(0., t.max);
+
+        *air <= 0.
+    }
+
+    pub fn pilot_fraction(&self) -> f32 {
+        self.fraction(self.pilot)
+    }
+
+    pub fn fraction(&self, air: f32) -> f32 {
+        // A tank tuned to hold nothing is always empty.
+        if self.tuning.max <= 0. {
+            return 0.;
+        }
+        (air / self.tuning.max).clamp(0., 1.)
+    }
+
+    // `diver` is the lowest tank fraction among found divers, if any.
+    pub fn is_low(&self, diver: Option<f32>) -> bool {
+        let warning = self.tuning.warning;
+        self.pilot_fraction() < warning || diver.is_some_and(|f| f < warning)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tuned(max: f32) -> Oxygen {
+        Oxygen::new(OxygenTuning {
+            max,
+            pilot_drain: 1.,
+            friend_drain: 1.,
+            friend_start: 35.,
+            refill: 10.,
+            warning: 0.25,
+        })
+    }
+
+    #[test]
+    fn a_tank_with_no_capacity_reads_empty() {
+        let oxygen = tuned(0.);
+        assert_eq!(oxygen.pilot_fraction(), 0.);
+        assert_eq!(oxygen.fraction(20.), 0.);
+        assert!(oxygen.is_low(None));
+
+        let oxygen = tuned(100.);
+        assert_eq!(oxygen.pilot_fraction(), 1.);
+        assert_eq!(oxygen.fraction(25.), 0.25);
+    }
+}
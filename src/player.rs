@@ -5,15 +5,36 @@ use notan::{
 };
 
 use crate::{
-    RENDER_HEIGHT, RENDER_WIDTH, Scene, camera::Camera2D, echo::Echo, get_bg_color, send_echo,
+    RENDER_HEIGHT, RENDER_WIDTH, Scene,
+    camera::Camera2D,
+    echo::Echo,
+    input::{Action, Controls},
+    material::Material,
+    sdf::DistanceField,
+    send_echo,
+    terrain::Terrain,
+    upgrades::SubStats,
 };
 
+pub struct Impact {
+    pub speed: f32,
+    pub material: Material,
+}
+
 pub struct Player {
     texture: Texture,
     pub pos: Vec2,
-    vel: Vec2,
-    dir: f32,
-    timer: f32,
+    pub vel: Vec2,
+    pub dir: f32,
+    pub timer: f32,
+    pub stats: SubStats,
+    // Set on the frames the sub bumps into a wall.
+    pub impact: Option<Impact>,
+    // Set from the battery before every update.
+    pub thrust_power: f32,
+    pub sonar_range: f32,
+    // How hard the motor worked this frame, 0 to 1.
+    pub throttle: f32,
 }
 
 impl Player {
@@ -28,50 +49,99 @@ impl Player {
             vel: Vec2::ZERO,
             dir: 0.,
             timer: 0.,
+            stats: SubStats::new(),
+            impact: None,
+            thrust_power: 1.,
+            sonar_range: f32::INFINITY,
+            throttle: 0.,
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn update(
         &mut self,
         app: &mut App,
-        bytes: &Vec<u8>,
+        controls: &Controls,
+        terrain: &Terrain,
+        field: &DistanceField,
         echoes: &mut Vec<Echo>,
         camera: &Camera2D,
         scene: &Scene,
-    ) {
-        if app.keyboard.is_down(notan::prelude::KeyCode::W) && *scene == Scene::Game {
-            self.vel += Vec2::from_angle(self.dir.to_radians()) * app.timer.delta_f32() * 1.;
+    ) -> bool {
+        let mut pinged = false;
+
+        self.throttle = 0.;
+        if *scene == Scene::Game {
+            let forward = Vec2::from_angle(self.dir.to_radians());
+            let thrust = controls.value(Action::Thrust) - controls.value(Action::Reverse) * 0.5;
+            let strafe = controls.axis(Action::StrafeLeft, Action::StrafeRight) * 0.5;
+            self.throttle = (thrust.abs() + strafe.abs()).min(1.) * self.thrust_power;
+            self.vel += (forward * thrust + forward.perp() * strafe)
+                * self.thrust_power
+                * app.timer.delta_f32()
+                * self.stats.acceleration;
         }
-        self.vel = self.vel.clamp_length_max(3.);
+        self.vel = self.vel.clamp_length_max(self.stats.max_speed);
         self.pos += self.vel;
 
         self.vel -= (self.vel / 100.) * app.timer.delta_f32() * 60.;
 
-        if app.keyboard.is_down(notan::prelude::KeyCode::D) && *scene == Scene::Game {
-            self.dir += app.timer.delta_f32() * 60.;
-        }
-        if app.keyboard.is_down(notan::prelude::KeyCode::A) && *scene == Scene::Game {
-            self.dir -= app.timer.delta_f32() * 60.;
+        if *scene == Scene::Game {
+            self.dir += controls.axis(Action::TurnLeft, Action::TurnRight)
+                * app.timer.delta_f32()
+                * self.stats.turn_rate;
         }
 
-        if app.keyboard.was_pressed(notan::prelude::KeyCode::Space)
+        if controls.was_pressed(Action::Ping)
             && *scene == Scene::Game
             && self.timer <= 0.
+            && self.sonar_range > 0.
         {
-            send_echo(echoes, &self.pos, self.dir, Color::PURPLE);
-            self.timer = 40.
+            send_echo(
+                echoes,
+                &self.pos,
+                self.dir,
+                Color::PURPLE,
+                true,
+                self.sonar_range,
+                self.stats.echo_rays,
+            );
+            self.timer = self.stats.echo_cooldown;
+            pinged = true;
         }
 
         self.timer -= app.timer.delta_f32() * 60.;
 
-        if get_bg_color(
-            bytes,
+        let center = Vec2::new(
             self.pos.x * (camera.work_size.x / (RENDER_WIDTH / 0.5)) + 16.,
             self.pos.y * (camera.work_size.y / (RENDER_HEIGHT / 0.5)) + 16.,
-        ) != Color::BLACK
-        {
-            self.vel *= -1.;
+        );
+
+        self.impact = None;
+        if field.distance(center) <= 0. {
+            self.impact = Some(Impact {
+                speed: self.vel.length(),
+                material: terrain.material(center.x, center.y),
+            });
+
+            // Bounce off the wall's surface, straight back where it's too ragged to tell.
+            let normal = field.normal(center);
+            if normal == Vec2::ZERO {
+                self.vel *= -1.;
+            } else if self.vel.dot(normal) < 0. {
+                self.vel -= 2. * self.vel.dot(normal) * normal;
+            }
         }
+
+        pinged
+    }
+
+    pub fn echo_ready(&self) -> f32 {
+        (1. - self.timer / self.stats.echo_cooldown).clamp(0., 1.)
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.vel.length() / self.stats.max_speed
     }
 
     pub fn render(&mut self, draw: &mut Draw) {
@@ -0,0 +1,639 @@
+use notan::{app::Color, math::Vec2};
+
+use crate::{
+    Scene, State, config,
+    echo::Echo,
+    entity::{Ai, Entity, Kind, SurvivorCondition},
+    events::Event,
+    hazard::Hazard,
+    hud,
+    level::parse_numbers,
+    tension::TensionDirector,
+};
+
+// Saves are plain text, one entry per line starting with a keyword, like the level data.
+// Bump VERSION whenever an entry changes and teach `parse` to read the older layout.
+pub const VERSION: u32 = 1;
+pub const SLOTS: usize = 3;
+
+// Entries every save has, the rest are lists that may be empty.
+const REQUIRED: [&str; 9] = [
+    "seed",
+    "scene",
+    "player",
+    "hull",
+    "battery",
+    "oxygen",
+    "time",
+    "beacons_left",
+    "torpedoes_left",
+];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Slot {
+    Auto,
+    Manual(usize),
+}
+
+impl Slot {
+    fn file_name(self) -> String {
+        match self {
+            Slot::Auto => "autosave.sav".to_string(),
+            Slot::Manual(i) => format!("save{}.sav", i + 1),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaveData {
+    pub seed: u64,
+    pub scene: Scene,
+    pub player_pos: Vec2,
+    pub player_vel: Vec2,
+    pub player_dir: f32,
+    pub player_timer: f32,
+    pub hull: f32,
+    pub battery: f32,
+    // The pilot's tank, survivors carry their own.
+    pub oxygen: f32,
+    pub time: f32,
+    pub beacons_left: i32,
+    pub torpedoes_left: i32,
+    pub fired: Vec<bool>,
+    pub items: Vec<String>,
+    pub reached: Vec<bool>,
+    pub cells: Vec<bool>,
+    pub salvage: Vec<bool>,
+    pub entities: Vec<Entity>,
+    // Where torpedoes went off and how much they broke.
+    pub blasts: Vec<(Vec2, f32)>,
+    pub echoes: Vec<Echo>,
+    pub revealed: Vec<(u32, u32, [u8; 3])>,
+}
+
+impl SaveData {
+    pub(crate) fn capture(state: &State) -> Self {
+        Self {
+            seed: fastrand::get_seed(),
+            scene: state.scene,
+            player_pos: state.player.pos,
+            player_vel: state.player.vel,
+            player_dir: state.player.dir,
+            player_timer: state.player.timer,
+            hull: state.hull.health,
+            battery: state.battery.charge,
+            oxygen: state.oxygen.pilot,
+            time: state.run_time,
+            beacons_left: state.num_of_beacons,
+            torpedoes_left: state.num_of_torpedoes,
+            fired: state.triggers.iter().map(|t| t.fired).collect(),
+            items: state.items.clone(),
+            reached: state.checkpoints.iter().map(|c| c.reached).collect(),
+            cells: state.power_cells.iter().map(|c| c.taken).collect(),
+            salvage: state.salvage.iter().map(|s| s.taken).collect(),
+            entities: state.entities.list.clone(),
+            blasts: state.blasts.clone(),
+            echoes: state.echoes.clone(),
+            revealed: state.minimap.revealed(),
+        }
+    }
+
+    pub(crate) fn apply(&self, state: &mut State) {
+        fastrand::seed(self.seed);
+        crate::set_scene(state, self.scene);
+        state.player.pos = self.player_pos;
+        state.player.vel = self.player_vel;
+        state.player.dir = self.player_dir;
+        state.player.timer = self.player_timer;
+        state.hull.health = self.hull.min(state.hull.tuning.max);
+        state.battery.charge = self.battery.min(state.battery.tuning.max);
+        state.oxygen.pilot = self.oxygen.min(state.oxygen.tuning.max);
+        state.run_time = self.time;
+        state.num_of_beacons = self.beacons_left;
+        state.num_of_torpedoes = self.torpedoes_left;
+        state.blasts = self.blasts.clone();
+        state.entities.list = restore_entities(&self.entities, &state.entities.list);
+        crate::rebuild_terrain(state);
+        state.items = self.items.clone();
+        for (i, trigger) in state.triggers.iter_mut().enumerate() {
+            trigger.fired = self.fired.get(i).copied().unwrap_or(false);
+        }
+        for (i, checkpoint) in state.checkpoints.iter_mut().enumerate() {
+            checkpoint.reached = self.reached.get(i).copied().unwrap_or(false);
+        }
+        for (i, cell) in state.power_cells.iter_mut().enumerate() {
+            cell.taken = self.cells.get(i).copied().unwrap_or(false);
+        }
+        for (i, item) in state.salvage.iter_mut().enumerate() {
+            item.taken = self.salvage.get(i).copied().unwrap_or(false);
+        }
+        state.echoes = self.echoes.clone();
+        state.minimap.restore(&self.revealed);
+        state.messages.clear();
+        state.tension = TensionDirector::new();
+    }
+
+    pub fn serialize(&self) -> String {
+        let mut text = format!("# Blind Depths save\nversion {VERSION}\n");
+        let mut line = |entry: String| {
+            text.push_str(&entry);
+            text.push('\n');
+        };
+
+        line(format!("seed {}", self.seed));
+        line(format!("scene {}", scene_name(self.scene)));
+        line(format!(
+            "player {} {} {} {} {} {}",
+            self.player_pos.x,
+            self.player_pos.y,
+            self.player_vel.x,
+            self.player_vel.y,
+            self.player_dir,
+            self.player_timer
+        ));
+        line(format!("hull {}", self.hull));
+        line(format!("battery {}", self.battery));
+        line(format!("oxygen {}", self.oxygen));
+        line(format!("time {}", self.time));
+        line(format!("beacons_left {}", self.beacons_left));
+        line(format!("torpedoes_left {}", self.torpedoes_left));
+
+        let fired: Vec<String> = self.fired.iter().map(|f| (*f as u8).to_string()).collect();
+        line(format!("fired {}", fired.join(" ")));
+        line(format!("items {}", self.items.join(" ")));
+
+        let reached: Vec<String> = self
+            .reached
+            .iter()
+            .map(|r| (*r as u8).to_string())
+            .collect();
+        line(format!("reached {}", reached.join(" ")));
+
+        let cells: Vec<String> = self.cells.iter().map(|c| (*c as u8).to_string()).collect();
+        line(format!("cells {}", cells.join(" ")));
+
+        let salvage: Vec<String> = self
+            .salvage
+            .iter()
+            .map(|s| (*s as u8).to_string())
+            .collect();
+        line(format!("salvage {}", salvage.join(" ")));
+
+        for (pos, radius) in &self.blasts {
+            line(format!("blast {} {} {radius}", pos.x, pos.y));
+        }
+
+        for e in &self.entities {
+            let pos = e.pos();
+            let state = match (&e.ai, &e.emitter, &e.hazard) {
+                (
+                    Some(Ai::Survivor {
+                        found,
+                        condition,
+                        air,
+                    }),
+                    _,
+                    _,
+                ) => format!(
+                    "{} {} {} {air}",
+                    *found as u8,
+                    e.is_visible() as u8,
+                    *condition as u8
+                ),
+                (Some(Ai::Creature { awake, .. }), _, _) => (*awake as u8).to_string(),
+                (None, Some(b), _) => format!("{} {} {}", b.visible as u8, b.freq, b.timer),
+                (_, _, Some(Hazard::Rockfall { radius, fallen })) => {
+                    format!("{radius} {}", *fallen as u8)
+                }
+                _ => continue,
+            };
+            line(format!("{} {} {} {state}", e.kind.key(), pos.x, pos.y));
+        }
+
+        for e in &self.echoes {
+            let c = e.hit_color;
+            line(format!(
+                "echo {} {} {} {} {} {} {} {} {} {} {} {}",
+                e.pos.x,
+                e.pos.y,
+                e.dir,
+                e.hit as u8,
+                e.no_find as u8,
+                e.lifetime,
+                e.audible as u8,
+                c.r,
+                c.g,
+                c.b,
+                c.a,
+                e.range
+            ));
+        }
+
+        for (x, y, [r, g, b]) in &self.revealed {
+            line(format!("cell {x} {y} {r:02x}{g:02x}{b:02x}"));
+        }
+
+        text
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut data = Self::empty();
+        let mut version = None;
+        let mut seen = vec![];
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(' ').unwrap_or((line, ""));
+            if keyword != "version" && version.is_none() {
+                return Err(format!("save line {}: missing version", i + 1));
+            }
+            seen.push(keyword);
+
+            match keyword {
+                "version" => parse_version(rest).map(|v| version = Some(v)),
+                "seed" => rest
+                    .parse()
+                    .map(|seed| data.seed = seed)
+                    .map_err(|_| format!("bad seed `{rest}`")),
+                "scene" => parse_scene(rest).map(|scene| data.scene = scene),
+                "cell" => parse_cell(rest).map(|cell| data.revealed.push(cell)),
+                "items" => {
+                    data.items = rest.split_whitespace().map(str::to_string).collect();
+                    Ok(())
+                }
+                _ => parse_numbers(rest).and_then(|n| data.read_entry(keyword, &n)),
+            }
+            .map_err(|e| format!("save line {}: {e}", i + 1))?;
+        }
+
+        version.ok_or("empty save file")?;
+        if let Some(missing) = REQUIRED.iter().find(|k| !seen.contains(k)) {
+            return Err(format!("save has no `{missing}`"));
+        }
+        Ok(data)
+    }
+
+    fn read_entry(&mut self, keyword: &str, n: &[f32]) -> Result<(), String> {
+        match (keyword, n) {
+            ("player", [x, y, vx, vy, dir, timer]) => {
+                self.player_pos = Vec2::new(*x, *y);
+                self.player_vel = Vec2::new(*vx, *vy);
+                self.player_dir = *dir;
+                self.player_timer = *timer;
+            }
+            ("hull", [health]) => self.hull = *health,
+            ("battery", [charge]) => self.battery = *charge,
+            ("oxygen", [pilot]) => self.oxygen = *pilot,
+            ("time", [seconds]) => self.time = *seconds,
+            ("beacons_left", [count]) => self.beacons_left = *count as i32,
+            ("torpedoes_left", [count]) => self.torpedoes_left = *count as i32,
+            ("blast", [x, y, radius]) => self.blasts.push((Vec2::new(*x, *y), *radius)),
+            ("survivor", [x, y, found, show, condition, air]) => {
+                let condition = SurvivorCondition::ALL
+                    .get(*condition as usize)
+                    .copied()
+                    .ok_or(format!("bad survivor condition `{condition}`"))?;
+                let mut survivor = Entity::survivor(Vec2::new(*x, *y), condition);
+                survivor.ai = Some(Ai::Survivor {
+                    found: *found != 0.,
+                    condition,
+                    air: *air,
+                });
+                if let Some(render) = &mut survivor.render {
+                    render.visible = *show != 0.;
+                }
+                self.entities.push(survivor);
+            }
+            ("creature", [x, y, awake]) => {
+                let mut creature = Entity::creature(Vec2::new(*x, *y));
+                if *awake != 0. {
+                    creature.ai = Some(Ai::Creature {
+                        awake: true,
+                        speed: 15.,
+                        lure: None,
+                    });
+                    if let Some(render) = &mut creature.render {
+                        render.visible = true;
+                    }
+                }
+                self.entities.push(creature);
+            }
+            ("fired", flags) => self.fired = flags.iter().map(|f| *f != 0.).collect(),
+            ("reached", flags) => self.reached = flags.iter().map(|f| *f != 0.).collect(),
+            ("cells", flags) => self.cells = flags.iter().map(|f| *f != 0.).collect(),
+            ("salvage", flags) => self.salvage = flags.iter().map(|f| *f != 0.).collect(),
+            ("rockfall", [x, y, radius, fallen]) => {
+                let mut rockfall = Entity::rockfall(Vec2::new(*x, *y), *radius);
+                rockfall.hazard = Some(Hazard::Rockfall {
+                    radius: *radius,
+                    fallen: *fallen != 0.,
+                });
+                self.entities.push(rockfall);
+            }
+            ("beacon", [x, y, visible, freq, timer]) => {
+                let mut beacon = Entity::beacon(Vec2::new(*x, *y), *visible != 0., *freq as i32);
+                if let Some(emitter) = &mut beacon.emitter {
+                    emitter.timer = *timer;
+                }
+                self.entities.push(beacon);
+            }
+            (
+                "echo",
+                [
+                    x,
+                    y,
+                    dir,
+                    hit,
+                    no_find,
+                    lifetime,
+                    audible,
+                    r,
+                    g,
+                    b,
+                    a,
+                    range,
+                ],
+            ) => {
+                let mut echo = Echo::new(*x, *y, *dir, Color::new(*r, *g, *b, *a));
+                echo.hit = *hit != 0.;
+                echo.no_find = *no_find != 0.;
+                echo.lifetime = *lifetime;
+                echo.audible = *audible != 0.;
+                echo.range = *range;
+                self.echoes.push(echo);
+            }
+            (
+                "player" | "hull" | "battery" | "oxygen" | "time" | "beacons_left"
+                | "torpedoes_left" | "blast" | "survivor" | "creature" | "beacon" | "rockfall"
+                | "echo",
+                _,
+            ) => {
+                return Err(format!("wrong number of values for `{keyword}`"));
+            }
+            _ => return Err(format!("unknown entry `{keyword}`")),
+        }
+
+        Ok(())
+    }
+
+    fn empty() -> Self {
+        Self {
+            seed: 0,
+            scene: Scene::Game,
+            player_pos: Vec2::ZERO,
+            player_vel: Vec2::ZERO,
+            player_dir: 0.,
+            player_timer: 0.,
+            hull: 0.,
+            battery: 0.,
+            oxygen: 0.,
+            time: 0.,
+            beacons_left: 0,
+            torpedoes_left: 0,
+            fired: vec![],
+            items: vec![],
+            reached: vec![],
+            cells: vec![],
+            salvage: vec![],
+            entities: vec![],
+            blasts: vec![],
+            echoes: vec![],
+            revealed: vec![],
+        }
+    }
+}
+
+// Logs belong to the profile rather than the run, so the ones still lying around stay
+// where they are and a checkpoint snapshot's copies are dropped. The same goes for the
+// currents and vents that never change.
+fn restore_entities(saved: &[Entity], current: &[Entity]) -> Vec<Entity> {
+    let kept = |e: &Entity| matches!(e.kind, Kind::Log | Kind::Current | Kind::Vent);
+
+    let mut list: Vec<Entity> = saved.iter().filter(|e| !kept(e)).cloned().collect();
+    list.extend(current.iter().filter(|e| e.alive && kept(e)).cloned());
+    list
+}
+
+// Keeps the autosave slot at the last rescue.
+pub(crate) fn autosave(state: &State, event: &Event) {
+    if let Event::FriendFound { .. } = event {
+        write(Slot::Auto, &SaveData::capture(state));
+    }
+}
+
+pub fn write(slot: Slot, data: &SaveData) {
+    config::write(&slot.file_name(), &data.serialize());
+}
+
+pub fn read(slot: Slot) -> Result<SaveData, String> {
+    let text = config::read(&slot.file_name()).ok_or("no save in this slot")?;
+    SaveData::parse(&text)
+}
+
+pub fn exists(slot: Slot) -> bool {
+    config::read(&slot.file_name()).is_some()
+}
+
+// Short description of a slot for the save and load menus.
+pub fn summary(slot: Slot) -> String {
+    match read(slot) {
+        Ok(data) => format!("{:.0}m", hud::depth(data.player_pos.y)),
+        Err(_) if exists(slot) => "DAMAGED".to_string(),
+        Err(_) => "EMPTY".to_string(),
+    }
+}
+
+fn parse_version(text: &str) -> Result<u32, String> {
+    let version: u32 = text.parse().map_err(|_| format!("bad version `{text}`"))?;
+
+    if version > VERSION {
+        return Err(format!("save version {version} is newer than this game"));
+    }
+    Ok(version)
+}
+
+fn parse_cell(text: &str) -> Result<(u32, u32, [u8; 3]), String> {
+    let bad = || format!("bad cell `{text}`");
+    let [x, y, color] = text.split_whitespace().collect::<Vec<_>>()[..] else {
+        return Err(bad());
+    };
+
+    let x = x.parse().map_err(|_| bad())?;
+    let y = y.parse().map_err(|_| bad())?;
+    let rgb = u32::from_str_radix(color, 16).map_err(|_| bad())?;
+    if color.len() != 6 {
+        return Err(bad());
+    }
+
+    Ok((x, y, [(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8]))
+}
+
+fn scene_name(scene: Scene) -> &'static str {
+    match scene {
+        Scene::Start => "start",
+        Scene::Game => "game",
+        Scene::End => "end",
+    }
+}
+
+fn parse_scene(text: &str) -> Result<Scene, String> {
+    match text {
+        "start" => Ok(Scene::Start),
+        "game" => Ok(Scene::Game),
+        "end" => Ok(Scene::End),
+        _ => Err(format!("bad scene `{text}`")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The level's entities as `capture` would snapshot them.
+    fn level() -> Vec<Entity> {
+        vec![
+            Entity::survivor(Vec2::new(300., 400.), SurvivorCondition::Calm),
+            Entity::log(Vec2::new(50., 60.), 0),
+            Entity::log(Vec2::new(70., 80.), 1),
+            Entity::current(Vec2::new(500., 500.), 80., Vec2::new(1., 0.)),
+            Entity::vent(Vec2::new(900., 300.), 40.),
+            Entity::rockfall(Vec2::new(700., 80.), 60.),
+        ]
+    }
+
+    fn count(list: &[Entity], kind: Kind) -> usize {
+        list.iter().filter(|e| e.alive && e.kind == kind).count()
+    }
+
+    #[test]
+    fn respawning_keeps_one_copy_of_each_log() {
+        let checkpoint = level();
+        let once = restore_entities(&checkpoint, &level());
+        let twice = restore_entities(&checkpoint, &once);
+
+        assert_eq!(count(&once, Kind::Log), 2);
+        assert_eq!(count(&twice, Kind::Log), 2);
+    }
+
+    #[test]
+    fn logs_collected_after_the_checkpoint_stay_collected() {
+        let checkpoint = level();
+        let mut current = level();
+        current[1].alive = false;
+
+        let restored = restore_entities(&checkpoint, &current);
+        let logs: Vec<Option<usize>> = restored
+            .iter()
+            .filter(|e| e.kind == Kind::Log)
+            .map(|e| e.lore)
+            .collect();
+        assert_eq!(logs, [Some(1)]);
+    }
+
+    #[test]
+    fn respawning_keeps_one_copy_of_each_current_and_vent() {
+        let checkpoint = level();
+        let once = restore_entities(&checkpoint, &level());
+        let twice = restore_entities(&checkpoint, &once);
+
+        for kind in [Kind::Current, Kind::Vent] {
+            assert_eq!(count(&once, kind), 1);
+            assert_eq!(count(&twice, kind), 1);
+        }
+    }
+
+    #[test]
+    fn loaded_saves_get_currents_and_vents_from_the_level() {
+        // Save files leave them out, the level's own are kept.
+        let saved = SaveData::parse(&mid_game().serialize()).unwrap();
+        let restored = restore_entities(&saved.entities, &level());
+
+        assert_eq!(count(&restored, Kind::Current), 1);
+        assert_eq!(count(&restored, Kind::Vent), 1);
+        assert_eq!(count(&restored, Kind::Rockfall), 1);
+    }
+
+    fn mid_game() -> SaveData {
+        let mut survivor = Entity::survivor(Vec2::new(300., 400.), SurvivorCondition::ALL[1]);
+        survivor.ai = Some(Ai::Survivor {
+            found: true,
+            condition: SurvivorCondition::ALL[1],
+            air: 42.5,
+        });
+        if let Some(render) = &mut survivor.render {
+            render.visible = true;
+        }
+
+        let mut creature = Entity::creature(Vec2::new(-20.5, 900.));
+        creature.ai = Some(Ai::Creature {
+            awake: true,
+            speed: 15.,
+            lure: None,
+        });
+        if let Some(render) = &mut creature.render {
+            render.visible = true;
+        }
+
+        let mut beacon = Entity::beacon(Vec2::new(10., 20.), true, 3);
+        if let Some(emitter) = &mut beacon.emitter {
+            emitter.timer = 0.25;
+        }
+
+        let mut rockfall = Entity::rockfall(Vec2::new(700., 80.), 60.);
+        rockfall.hazard = Some(Hazard::Rockfall {
+            radius: 60.,
+            fallen: true,
+        });
+
+        let mut echo = Echo::new(1.5, 2.25, 0.3, Color::new(0.1, 0.2, 0.3, 1.));
+        echo.hit = true;
+        echo.lifetime = 0.8;
+        echo.audible = true;
+        echo.range = 150.;
+
+        let mut data = SaveData::empty();
+        data.seed = 1234567890123;
+        data.scene = Scene::Game;
+        data.player_pos = Vec2::new(812.25, 1033.5);
+        data.player_vel = Vec2::new(-0.75, 1.125);
+        data.player_dir = 2.2;
+        data.player_timer = 12.;
+        data.hull = 64.5;
+        data.battery = 30.;
+        data.oxygen = 71.25;
+        data.time = 321.75;
+        data.beacons_left = 1;
+        data.torpedoes_left = 1;
+        data.fired = vec![true, false, true];
+        data.items = vec!["key".to_string(), "chart".to_string()];
+        data.reached = vec![true, false];
+        data.cells = vec![false, true];
+        data.salvage = vec![true];
+        data.entities = vec![survivor, creature, beacon, rockfall];
+        data.blasts = vec![(Vec2::new(640., 512.), 28.)];
+        data.echoes = vec![echo, Echo::new(5., 6., 3.1, Color::WHITE)];
+        data.revealed = vec![(3, 4, [0xa0, 0xb1, 0xc2]), (5, 0, [0, 0, 0xff])];
+        data
+    }
+
+    #[test]
+    fn a_mid_game_save_survives_a_round_trip() {
+        let data = mid_game();
+        assert_eq!(SaveData::parse(&data.serialize()), Ok(data));
+    }
+
+    #[test]
+    fn broken_or_newer_saves_are_rejected() {
+        assert!(SaveData::parse("").is_err());
+        assert!(SaveData::parse("seed 1\nversion 1\n").is_err());
+        assert!(SaveData::parse("version 1\nseed 1\n").is_err());
+        assert!(SaveData::parse(&format!("version {}\n", VERSION + 1)).is_err());
+        assert!(SaveData::parse("version 1\nplayer 1 2\n").is_err());
+        assert!(SaveData::parse("version 1\nwarp 1\n").is_err());
+        assert!(SaveData::parse("version 1\ncell 1 2 zzzzzz\n").is_err());
+    }
+}
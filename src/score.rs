@@ -0,0 +1,72 @@
+use crate::entity::SurvivorCondition;
+
+const RESCUE_POINTS: i32 = 1000;
+// Points for every second left of the par time.
+const PAR_SECONDS: f32 = 600.;
+const TIME_POINTS: f32 = 2.;
+const BEACON_COST: i32 = 100;
+const CELL_COST: i32 = 50;
+
+// What the run looked like when it ended.
+pub struct RunSummary {
+    pub rescued: Vec<SurvivorCondition>,
+    pub survivors: usize,
+    pub seconds: f32,
+    pub beacons_used: i32,
+    pub cells_used: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub rescued: usize,
+    pub survivors: usize,
+    pub rescues: i32,
+    pub time: i32,
+    pub resources: i32,
+}
+
+// Divers in a worse state are worth more.
+pub fn rescue_points(condition: SurvivorCondition) -> i32 {
+    RESCUE_POINTS
+        + match condition {
+            SurvivorCondition::Calm => 0,
+            SurvivorCondition::Injured => 500,
+            SurvivorCondition::Panicking => 300,
+            SurvivorCondition::LowOxygen => 400,
+        }
+}
+
+impl Score {
+    pub fn new(run: &RunSummary) -> Self {
+        // Finishing fast only counts when somebody was brought along.
+        let time = if run.rescued.is_empty() {
+            0
+        } else {
+            ((PAR_SECONDS - run.seconds).max(0.) * TIME_POINTS) as i32
+        };
+
+        Self {
+            rescued: run.rescued.len(),
+            survivors: run.survivors,
+            rescues: run.rescued.iter().map(|c| rescue_points(*c)).sum(),
+            time,
+            resources: -(run.beacons_used * BEACON_COST + run.cells_used as i32 * CELL_COST),
+        }
+    }
+
+    pub fn total(&self) -> i32 {
+        (self.rescues + self.time + self.resources).max(0)
+    }
+
+    pub fn text(&self) -> String {
+        format!(
+            "RESCUED {}/{}   {}\nTIME BONUS   {}\nRESOURCES USED   {}\nSCORE   {}",
+            self.rescued,
+            self.survivors,
+            self.rescues,
+            self.time,
+            self.resources,
+            self.total()
+        )
+    }
+}
@@ -0,0 +1,389 @@
+use notan::math::Vec2;
+
+use crate::terrain::{Region, Terrain};
+
+// Distances are only tracked this far, which keeps updates after an edit local.
+pub const MAX_DISTANCE: f32 = 64.;
+
+// Signed distance from every cave pixel to the nearest wall: positive in the water,
+// negative inside walls. Lets echoes and the sub skip the per-pixel checks while they
+// are far from anything.
+pub struct DistanceField {
+    pub width: usize,
+    pub height: usize,
+    distances: Vec<f32>,
+}
+
+impl DistanceField {
+    pub fn new(terrain: &Terrain) -> Self {
+        let mut field = Self {
+            width: terrain.width,
+            height: terrain.height,
+            distances: vec![0.; terrain.width * terrain.height],
+        };
+        field.update(
+            terrain,
+            Region {
+                x0: 0,
+                y0: 0,
+                x1: terrain.width,
+                y1: terrain.height,
+            },
+        );
+        field
+    }
+
+    // Outside the cave counts as deep inside a wall.
+    pub fn distance(&self, pos: Vec2) -> f32 {
+        if pos.x < 0. || pos.y < 0. {
+            return -MAX_DISTANCE;
+        }
+
+        let (x, y) = (pos.x as usize, pos.y as usize);
+        if x >= self.width || y >= self.height {
+            return -MAX_DISTANCE;
+        }
+        self.distances[y * self.width + x]
+    }
+
+    // Points away from the nearest wall, zero where the field is flat.
+    pub fn normal(&self, pos: Vec2) -> Vec2 {
+        let dx = self.distance(pos + Vec2::X * 2.) - self.distance(pos - Vec2::X * 2.);
+        let dy = self.distance(pos + Vec2::Y * 2.) - self.distance(pos - Vec2::Y * 2.);
+        Vec2::new(dx, dy).normalize_or_zero()
+    }
+
+    // Recomputes the field around `region` after the terrain inside it changed.
+    pub fn update(&mut self, terrain: &Terrain, region: Region) {
+        let margin = MAX_DISTANCE as usize + 1;
+        let target = self.grow(region, margin);
+        // Every pixel that can affect a distance in `target` is within `margin` of it.
+        let window = self.grow(target, margin);
+
+        let width = window.x1 - window.x0;
+        let height = window.y1 - window.y0;
+        let solid: Vec<bool> = (window.y0..window.y1)
+            .flat_map(|y| (window.x0..window.x1).map(move |x| (x, y)))
+            .map(|(x, y)| !terrain.is_water(x, y))
+            .collect();
+
+        let to_wall = squared_distances(width, height, |i| solid[i]);
+        let to_water = squared_distances(width, height, |i| !solid[i]);
+
+        for y in target.y0..target.y1 {
+            for x in target.x0..target.x1 {
+                let i = (y - window.y0) * width + (x - window.x0);
+                let distance = if solid[i] {
+                    -to_water[i].sqrt()
+                } else {
+                    to_wall[i].sqrt()
+                };
+                self.distances[y * self.width + x] = distance.clamp(-MAX_DISTANCE, MAX_DISTANCE);
+            }
+        }
+    }
+
+    fn grow(&self, region: Region, by: usize) -> Region {
+        Region {
+            x0: region.x0.saturating_sub(by),
+            y0: region.y0.saturating_sub(by),
+            x1: (region.x1 + by).min(self.width),
+            y1: (region.y1 + by).min(self.height),
+        }
+    }
+}
+
+// Exact squared distance from every cell of a `width` by `height` grid to the nearest
+// cell where `is_feature` holds, one pass over the columns and one over the rows
+// (Felzenszwalb and Huttenlocher).
+pub fn squared_distances(
+    width: usize,
+    height: usize,
+    is_feature: impl Fn(usize) -> bool,
+) -> Vec<f32> {
+    let mut grid: Vec<f32> = (0..width * height)
+        .map(|i| if is_feature(i) { 0. } else { f32::INFINITY })
+        .collect();
+
+    let n = width.max(height);
+    let mut line = vec![0.; n];
+    let mut out = vec![0.; n];
+    let mut parabolas = vec![0; n];
+    let mut bounds = vec![0.; n + 1];
+
+    for x in 0..width {
+        for y in 0..height {
+            line[y] = grid[y * width + x];
+        }
+        transform_line(&line[..height], &mut out, &mut parabolas, &mut bounds);
+        for y in 0..height {
+            grid[y * width + x] = out[y];
+        }
+    }
+
+    for y in 0..height {
+        let row = &mut grid[y * width..(y + 1) * width];
+        line[..width].copy_from_slice(row);
+        transform_line(&line[..width], &mut out, &mut parabolas, &mut bounds);
+        row.copy_from_slice(&out[..width]);
+    }
+
+    grid
+}
+
+// 1D squared distance transform: `out[q]` is the lowest `(q - p)^2 + f[p]` over all `p`,
+// found as the lower envelope of the parabolas rooted at every finite `f[p]`.
+fn transform_line(f: &[f32], out: &mut [f32], parabolas: &mut [usize], bounds: &mut [f32]) {
+    let n = f.len();
+    let Some(first) = f.iter().position(|v| v.is_finite()) else {
+        out[..n].fill(f32::INFINITY);
+        return;
+    };
+
+    let intersection = |p: usize, q: usize| {
+        ((f[q] + (q * q) as f32) - (f[p] + (p * p) as f32)) / (2. * (q as f32 - p as f32))
+    };
+
+    let mut k = 0;
+    parabolas[0] = first;
+    bounds[0] = f32::NEG_INFINITY;
+    bounds[1] = f32::INFINITY;
+
+    for (q, value) in f.iter().enumerate().skip(first + 1) {
+        if !value.is_finite() {
+            continue;
+        }
+
+        let mut s = intersection(parabolas[k], q);
+        while s <= bounds[k] {
+            k -= 1;
+            s = intersection(parabolas[k], q);
+        }
+        k += 1;
+        parabolas[k] = q;
+        bounds[k] = s;
+        bounds[k + 1] = f32::INFINITY;
+    }
+
+    k = 0;
+    for (q, value) in out[..n].iter_mut().enumerate() {
+        while bounds[k + 1] < q as f32 {
+            k += 1;
+        }
+        let p = parabolas[k];
+        *value = (q as f32 - p as f32).powi(2) + f[p];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+    use crate::material::Material;
+
+    // Open water with `blobs` random rock circles, walled in when `blobs` isn't zero.
+    fn cave(width: usize, height: usize, blobs: usize, seed: u64) -> Terrain {
+        let water = [0, 0, 0, 255].repeat(width * height);
+        let mut terrain = Terrain::new(water, width, height);
+        if blobs > 0 {
+            let (w, h) = (width, height);
+            for (x0, y0, x1, y1) in [
+                (0, 0, w, 4),
+                (0, h - 4, w, h),
+                (0, 0, 4, h),
+                (w - 4, 0, w, h),
+            ] {
+                terrain.fill_region(Region { x0, y0, x1, y1 }, Material::Rock);
+            }
+        }
+        let mut rng = fastrand::Rng::with_seed(seed);
+        let far = Vec2::splat(-1000.);
+        for _ in 0..blobs {
+            let center = Vec2::new(rng.f32() * width as f32, rng.f32() * height as f32);
+            let radius = 2. + rng.f32() * width.min(height) as f32 / 8.;
+            terrain.fill_circle(center, radius, Material::Rock, far);
+        }
+        terrain.bake();
+        terrain
+    }
+
+    fn brute_force(width: usize, height: usize, is_feature: impl Fn(usize) -> bool) -> Vec<f32> {
+        (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as f32, (i / width) as f32);
+                (0..width * height)
+                    .filter(|j| is_feature(*j))
+                    .map(|j| {
+                        let (fx, fy) = ((j % width) as f32, (j / width) as f32);
+                        (x - fx).powi(2) + (y - fy).powi(2)
+                    })
+                    .fold(f32::INFINITY, f32::min)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn squared_distances_match_brute_force() {
+        let mut rng = fastrand::Rng::with_seed(7);
+        for (width, height, density) in [(23, 17, 0.05), (9, 31, 0.3), (16, 16, 0.)] {
+            let features: Vec<bool> = (0..width * height).map(|_| rng.f32() < density).collect();
+            assert_eq!(
+                squared_distances(width, height, |i| features[i]),
+                brute_force(width, height, |i| features[i])
+            );
+        }
+    }
+
+    #[test]
+    fn transform_line_matches_brute_force() {
+        let f = [
+            f32::INFINITY,
+            4.,
+            f32::INFINITY,
+            f32::INFINITY,
+            0.,
+            9.,
+            f32::INFINITY,
+        ];
+        let mut out = [0.; 7];
+        transform_line(&f, &mut out, &mut [0; 7], &mut [0.; 8]);
+
+        for (q, value) in out.iter().enumerate() {
+            let expected = f
+                .iter()
+                .enumerate()
+                .map(|(p, fp)| (q as f32 - p as f32).powi(2) + fp)
+                .fold(f32::INFINITY, f32::min);
+            assert_eq!(*value, expected);
+        }
+    }
+
+    #[test]
+    fn field_is_signed_and_clamped() {
+        let mut terrain = cave(40, 200, 0, 0);
+        terrain.fill_region(
+            Region {
+                x0: 0,
+                y0: 100,
+                x1: 40,
+                y1: 200,
+            },
+            Material::Rock,
+        );
+        let field = DistanceField::new(&terrain);
+
+        assert_eq!(field.distance(Vec2::new(20., 90.)), 10.);
+        // Measured to the last row of water, one above the wall.
+        assert_eq!(field.distance(Vec2::new(20., 110.)), -11.);
+        assert_eq!(field.distance(Vec2::new(20., 0.)), MAX_DISTANCE);
+        assert_eq!(field.distance(Vec2::new(-1., 50.)), -MAX_DISTANCE);
+        assert_eq!(field.distance(Vec2::new(20., 200.)), -MAX_DISTANCE);
+    }
+
+    #[test]
+    fn normal_points_away_from_a_flat_wall() {
+        let mut terrain = cave(64, 200, 0, 0);
+        terrain.fill_region(
+            Region {
+                x0: 0,
+                y0: 150,
+                x1: 64,
+                y1: 200,
+            },
+            Material::Rock,
+        );
+        let field = DistanceField::new(&terrain);
+
+        assert_eq!(field.normal(Vec2::new(32., 140.)), Vec2::new(0., -1.));
+        assert_eq!(field.normal(Vec2::new(32., 160.)), Vec2::new(0., -1.));
+        // Out of range of every wall the field is flat.
+        assert_eq!(field.normal(Vec2::new(32., 20.)), Vec2::ZERO);
+    }
+
+    #[test]
+    fn updating_a_dirty_region_matches_a_full_rebuild() {
+        let mut terrain = cave(300, 260, 40, 3);
+        let mut field = DistanceField::new(&terrain);
+
+        terrain.carve_circle(Vec2::new(150., 130.), 30., |_| true);
+        terrain.fill_circle(Vec2::new(20., 240.), 12., Material::Shale, Vec2::ZERO);
+        for region in terrain.take_dirty() {
+            field.update(&terrain, region);
+        }
+
+        assert_eq!(field.distances, DistanceField::new(&terrain).distances);
+    }
+
+    // Where a ray from `pos` first hits rock, stepping one pixel at a time like echoes
+    // used to.
+    fn march(terrain: &Terrain, pos: Vec2, dir: Vec2, max: f32) -> f32 {
+        let mut t = 0.;
+        while t < max {
+            let p = pos + dir * t;
+            if terrain.color(p.x, p.y) != notan::app::Color::BLACK {
+                break;
+            }
+            t += 1.;
+        }
+        t
+    }
+
+    fn trace(field: &DistanceField, pos: Vec2, dir: Vec2, max: f32) -> f32 {
+        let mut t = 0.;
+        while t < max {
+            let clear = field.distance(pos + dir * t);
+            if clear <= 0. {
+                break;
+            }
+            t += clear.max(1.);
+        }
+        t.min(max)
+    }
+
+    // A benchmark, not a check on timing: run with
+    // `cargo test --release -- --ignored --nocapture` and compare the printed times.
+    #[test]
+    #[ignore]
+    fn bench_tracing_against_pixel_stepping() {
+        let terrain = cave(2048, 2048, 300, 11);
+        let start = Instant::now();
+        let field = DistanceField::new(&terrain);
+        let build = start.elapsed();
+
+        let mut rng = fastrand::Rng::with_seed(5);
+        let rays: Vec<(Vec2, Vec2)> = std::iter::repeat_with(|| {
+            let pos = Vec2::new(rng.f32() * 2048., rng.f32() * 2048.);
+            (pos, Vec2::from_angle(rng.f32() * std::f32::consts::TAU))
+        })
+        .filter(|(pos, _)| field.distance(*pos) > 0.)
+        .take(20_000)
+        .collect();
+
+        let start = Instant::now();
+        let marched: Vec<f32> = rays
+            .iter()
+            .map(|(p, d)| march(&terrain, *p, *d, 1500.))
+            .collect();
+        let stepping = start.elapsed();
+
+        let start = Instant::now();
+        let traced: Vec<f32> = rays
+            .iter()
+            .map(|(p, d)| trace(&field, *p, *d, 1500.))
+            .collect();
+        let tracing = start.elapsed();
+
+        let agree = marched
+            .iter()
+            .zip(&traced)
+            .filter(|(m, t)| (*m - *t).abs() <= 2.)
+            .count();
+        println!(
+            "field built in {build:?}, {} rays: pixel stepping {stepping:?}, tracing {tracing:?}, {agree} hit within 2px",
+            rays.len()
+        );
+
+        assert!(agree as f32 >= rays.len() as f32 * 0.98);
+    }
+}
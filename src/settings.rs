@@ -0,0 +1,229 @@
+use std::collections::HashMap;
+
+use notan::log;
+
+use crate::{
+    config,
+    input::{self, Action, Bindings},
+    keys,
+    sound::Bus,
+};
+
+const SETTINGS_FILE: &str = "settings.cfg";
+// Where the volumes were kept before there was a settings file.
+const AUDIO_FILE: &str = "audio.cfg";
+
+pub const RESOLUTIONS: [(u32, u32); 5] = [
+    (1280, 720),
+    (1440, 810),
+    (1600, 900),
+    (1920, 1080),
+    (2560, 1440),
+];
+pub const TEXT_SCALES: [f32; 3] = [1., 1.25, 1.5];
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub resolution: (u32, u32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    pub volumes: [f32; 4],
+    pub bindings: Bindings,
+    pub text_scale: f32,
+    pub high_contrast: bool,
+}
+
+impl Settings {
+    pub fn new() -> Self {
+        Self {
+            resolution: (1440, 810),
+            fullscreen: false,
+            vsync: true,
+            volumes: [1.; 4],
+            bindings: input::default_bindings(),
+            text_scale: 1.,
+            high_contrast: false,
+        }
+    }
+
+    pub fn load() -> Self {
+        if let Some(text) = config::read(SETTINGS_FILE) {
+            return Self::from_values(&config::parse(&text));
+        }
+
+        // First run since settings moved to their own file: carry the old volumes over
+        // once, the next launch finds the settings file.
+        let audio = config::load(AUDIO_FILE);
+        let settings = Self::from_audio_values(&audio);
+        if !audio.is_empty() {
+            settings.save();
+        }
+        settings
+    }
+
+    pub fn save(&self) {
+        config::save(SETTINGS_FILE, &self.to_values());
+    }
+
+    // Every entry is checked on its own, so a single broken line only resets that setting.
+    pub fn from_values(values: &HashMap<String, String>) -> Self {
+        let default = Self::new();
+
+        let resolution = read(values, "resolution", default.resolution, |v| {
+            let (w, h) = v.split_once('x')?;
+            let res = (w.trim().parse().ok()?, h.trim().parse().ok()?);
+            RESOLUTIONS.contains(&res).then_some(res)
+        });
+
+        let volumes = Bus::ALL.map(|bus| {
+            let key = format!("volume.{}", bus.key());
+            read(values, &key, 1., |v| {
+                v.parse::<f32>()
+                    .ok()
+                    .filter(|v| v.is_finite())
+                    .map(|v| v.clamp(0., 1.))
+            })
+        });
+
+        let bindings = Action::ALL.map(|action| {
+            let key = format!("bind.{}", action.key());
+            read(values, &key, action.default_keys().to_vec(), |v| {
+                let mut keys = vec![];
+                for name in v.split(',') {
+                    keys.push(keys::from_name(name.trim())?);
+                }
+                (!keys.is_empty()).then_some(keys)
+            })
+        });
+
+        Self {
+            resolution,
+            fullscreen: read(values, "fullscreen", default.fullscreen, parse_bool),
+            vsync: read(values, "vsync", default.vsync, parse_bool),
+            volumes,
+            bindings,
+            text_scale: read(values, "text_scale", default.text_scale, |v| {
+                v.parse::<f32>().ok().filter(|s| TEXT_SCALES.contains(s))
+            }),
+            high_contrast: read(values, "high_contrast", default.high_contrast, parse_bool),
+        }
+    }
+
+    // The audio file stored each volume under the bare bus name.
+    pub fn from_audio_values(audio: &HashMap<String, String>) -> Self {
+        let values = Bus::ALL
+            .iter()
+            .filter_map(|bus| {
+                let volume = audio.get(bus.key())?;
+                Some((format!("volume.{}", bus.key()), volume.clone()))
+            })
+            .collect();
+        Self::from_values(&values)
+    }
+
+    pub fn to_values(&self) -> Vec<(String, String)> {
+        let mut values = vec![
+            (
+                "resolution".to_string(),
+                format!("{}x{}", self.resolution.0, self.resolution.1),
+            ),
+            ("fullscreen".to_string(), self.fullscreen.to_string()),
+            ("vsync".to_string(), self.vsync.to_string()),
+        ];
+
+        for bus in Bus::ALL {
+            values.push((
+                format!("volume.{}", bus.key()),
+                format!("{:.2}", self.volumes[bus as usize]),
+            ));
+        }
+
+        for action in Action::ALL {
+            let names: Vec<&str> = self.bindings[action as usize]
+                .iter()
+                .map(|k| keys::name(*k))
+                .collect();
+            values.push((format!("bind.{}", action.key()), names.join(", ")));
+        }
+
+        values.push(("text_scale".to_string(), self.text_scale.to_string()));
+        values.push(("high_contrast".to_string(), self.high_contrast.to_string()));
+
+        values
+    }
+
+    pub fn resolution_index(&self) -> usize {
+        RESOLUTIONS
+            .iter()
+            .position(|r| *r == self.resolution)
+            .unwrap_or(1)
+    }
+
+    pub fn text_scale_index(&self) -> usize {
+        TEXT_SCALES
+            .iter()
+            .position(|s| *s == self.text_scale)
+            .unwrap_or(0)
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn read<T>(
+    values: &HashMap<String, String>,
+    key: &str,
+    default: T,
+    parse: impl Fn(&str) -> Option<T>,
+) -> T {
+    let Some(value) = values.get(key) else {
+        return default;
+    };
+
+    parse(value).unwrap_or_else(|| {
+        log::warn!("Invalid setting `{key} = {value}`, using the default");
+        default
+    })
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value {
+        "true" | "on" | "1" => Some(true),
+        "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn volumes_carry_over_from_the_audio_file() {
+        let audio = config::parse("master = 0.40\nmusic = 0.25\nsfx = nope\n");
+        let settings = Settings::from_audio_values(&audio);
+
+        assert_eq!(settings.volumes, [0.4, 0.25, 1., 1.]);
+        assert_eq!(
+            Settings {
+                volumes: Settings::new().volumes,
+                ..settings
+            },
+            Settings::new()
+        );
+    }
+
+    #[test]
+    fn settings_survive_a_round_trip() {
+        let mut settings = Settings::new();
+        settings.resolution = (1920, 1080);
+        settings.volumes = [0.5, 0.25, 0.75, 0.];
+        settings.high_contrast = true;
+
+        let text = config::serialize(&settings.to_values());
+        assert_eq!(Settings::from_values(&config::parse(&text)), settings);
+    }
+}
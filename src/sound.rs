@@ -0,0 +1,406 @@
+use notan::{
+    app::App,
+    math::Vec2,
+    prelude::{AudioSource, Sound},
+};
+
+use crate::{
+    events::{DamageSource, Event},
+    spatial::{self, Cue, PAN_STEPS},
+    synth,
+};
+
+// How far music and ambience drop while ducked, and how quickly they get there and back.
+const DUCK_LEVEL: f32 = 0.3;
+const DUCK_ATTACK: f32 = 0.2;
+const DUCK_RELEASE: f32 = 0.01;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Bus {
+    Master,
+    Music,
+    Sfx,
+    Ambience,
+}
+
+impl Bus {
+    pub const ALL: [Bus; 4] = [Bus::Master, Bus::Music, Bus::Sfx, Bus::Ambience];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Bus::Master => "master",
+            Bus::Music => "music",
+            Bus::Sfx => "sfx",
+            Bus::Ambience => "ambience",
+        }
+    }
+}
+
+// Pure bus math, kept apart from the audio device so it can be driven and checked on its own.
+pub struct Mixer {
+    volumes: [f32; 4],
+    duck: f32,
+    duck_timer: f32,
+    fade: f32,
+    fade_target: f32,
+    fade_speed: f32,
+}
+
+impl Mixer {
+    pub fn new(volumes: [f32; 4]) -> Self {
+        Self {
+            volumes: volumes.map(|v| v.clamp(0., 1.)),
+            duck: 0.,
+            duck_timer: 0.,
+            fade: 1.,
+            fade_target: 1.,
+            fade_speed: 0.,
+        }
+    }
+
+    pub fn volume(&self, bus: Bus) -> f32 {
+        self.volumes[bus as usize]
+    }
+
+    pub fn set_volume(&mut self, bus: Bus, volume: f32) {
+        self.volumes[bus as usize] = volume.clamp(0., 1.);
+    }
+
+    pub fn duck(&mut self, frames: f32) {
+        self.duck_timer = self.duck_timer.max(frames);
+    }
+
+    pub fn set_fade(&mut self, fade: f32) {
+        self.fade = fade.clamp(0., 1.);
+        self.fade_target = self.fade;
+    }
+
+    pub fn fade_to(&mut self, target: f32, frames: f32) {
+        self.fade_target = target.clamp(0., 1.);
+        self.fade_speed = (self.fade_target - self.fade).abs() / frames.max(1.);
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        let step = dt * 60.;
+
+        if self.duck_timer > 0. {
+            self.duck_timer -= step;
+            self.duck = (self.duck + DUCK_ATTACK * step).min(1.);
+        } else {
+            self.duck = (self.duck - DUCK_RELEASE * step).max(0.);
+        }
+
+        if self.fade < self.fade_target {
+            self.fade = (self.fade + self.fade_speed * step).min(self.fade_target);
+        } else {
+            self.fade = (self.fade - self.fade_speed * step).max(self.fade_target);
+        }
+    }
+
+    pub fn gain(&self, bus: Bus) -> f32 {
+        let mut gain = self.volume(Bus::Master);
+
+        if bus != Bus::Master {
+            gain *= self.volume(bus);
+        }
+
+        if bus == Bus::Music || bus == Bus::Ambience {
+            gain *= 1. - self.duck * (1. - DUCK_LEVEL);
+            gain *= self.fade;
+        }
+
+        gain
+    }
+}
+
+pub struct SoundSystem {
+    pub sound_effects: [AudioSource; 10],
+    sound: [Option<Sound>; 10],
+    repeat: [bool; 10],
+    volume: [f32; 10],
+    bus: [Bus; 10],
+    ducks: [bool; 10],
+    cues: Vec<[AudioSource; PAN_STEPS]>,
+    cue_cooldown: [f32; Cue::ALL.len()],
+    pending: Vec<(Cue, Vec2, f32)>,
+    pub listener: Vec2,
+    pub mixer: Mixer,
+}
+
+impl SoundSystem {
+    pub fn new(app: &mut App, volumes: [f32; 4]) -> Self {
+        let m0 = app
+            .audio
+            .create_source(include_bytes!("assets/ambiance.mp3"))
+            .unwrap();
+        let m1 = app
+            .audio
+            .create_source(include_bytes!("assets/jumpscare.mp3"))
+            .unwrap();
+        let m2 = app
+            .audio
+            .create_source(include_bytes!("assets/creepy_cave.mp3"))
+            .unwrap();
+        let m3 = app
+            .audio
+            .create_source(include_bytes!("assets/monster.mp3"))
+            .unwrap();
+        let m4 = app
+            .audio
+            .create_source(include_bytes!("assets/echo_scary.wav"))
+            .unwrap();
+        let m5 = app
+            .audio
+            .create_source(include_bytes!("assets/more_scary.wav"))
+            .unwrap();
+        let m6 = app
+            .audio
+            .create_source(include_bytes!("assets/scary_sound.mp3"))
+            .unwrap();
+        let m7 = app
+            .audio
+            .create_source(include_bytes!("assets/short_scary.wav"))
+            .unwrap();
+        let m8 = app
+            .audio
+            .create_source(&synth::wav(&synth::drone(55., 4.), 1., 1.))
+            .unwrap();
+        let m9 = app
+            .audio
+            .create_source(&synth::wav(&synth::heartbeat(80.), 1., 1.))
+            .unwrap();
+
+        let cues = Cue::ALL
+            .iter()
+            .map(|cue| {
+                std::array::from_fn(|step| {
+                    app.audio
+                        .create_source(&cue.synth(spatial::step_pan(step)))
+                        .unwrap()
+                })
+            })
+            .collect();
+
+        Self {
+            sound_effects: [m0, m1, m2, m3, m4, m5, m6, m7, m8, m9],
+            sound: [None, None, None, None, None, None, None, None, None, None],
+            repeat: [
+                true, false, false, false, false, false, false, false, true, true,
+            ],
+            volume: [1., 1., 1., 1., 1., 1., 1., 1., 0., 0.],
+            bus: [
+                Bus::Ambience,
+                Bus::Sfx,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+                Bus::Music,
+            ],
+            ducks: [
+                false, true, false, false, false, false, false, false, false, false,
+            ],
+            cues,
+            cue_cooldown: [0.; Cue::ALL.len()],
+            pending: vec![],
+            listener: Vec2::ZERO,
+            mixer: Mixer::new(volumes),
+        }
+    }
+
+    pub fn play(&mut self, index: usize, app: &mut App) {
+        if self.ducks[index] {
+            self.mixer.duck(120.);
+        }
+
+        let sound = app.audio.play_sound(
+            &self.sound_effects[index],
+            self.volume[index] * self.mixer.gain(self.bus[index]),
+            self.repeat[index],
+        );
+        self.sound[index] = Some(sound);
+    }
+
+    pub fn set_volume(&mut self, index: usize, volume: f32) {
+        self.volume[index] = volume;
+    }
+
+    pub fn is_playing(&self, index: usize, app: &App) -> bool {
+        match &self.sound[index] {
+            Some(s) => !app.audio.is_stopped(s),
+            None => false,
+        }
+    }
+
+    pub fn play_at(&mut self, cue: Cue, pos: Vec2, app: &mut App) {
+        let gain = spatial::attenuation(self.listener, pos);
+        if gain < 0.02 || self.cue_cooldown[cue as usize] > 0. {
+            return;
+        }
+        self.cue_cooldown[cue as usize] = cue.spacing();
+
+        let step = spatial::pan_step(spatial::pan(self.listener, pos));
+        app.audio.play_sound(
+            &self.cues[cue as usize][step],
+            gain * self.mixer.gain(Bus::Sfx),
+            false,
+        );
+    }
+
+    pub fn play_delayed_at(&mut self, cue: Cue, pos: Vec2, delay: f32) {
+        self.pending.push((cue, pos, delay));
+    }
+
+    // The sounds the game's events make.
+    pub fn on_event(&mut self, event: &Event, app: &mut App) {
+        match *event {
+            Event::EchoHit {
+                pos, audible: true, ..
+            } => {
+                let delay = spatial::return_delay(self.listener, pos);
+                self.play_delayed_at(Cue::EchoReturn, pos, delay);
+            }
+            Event::Blast { pos, .. } | Event::Rockfall { pos, .. } => {
+                self.play_at(Cue::Impact, pos, app);
+            }
+            Event::PlayerDamaged {
+                source: DamageSource::Impact,
+                ..
+            } => self.play_at(Cue::Impact, self.listener, app),
+            Event::MonsterAwakened { .. } => {
+                self.play(1, app);
+                self.mixer.fade_to(0., 300.);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn update(&mut self, app: &mut App) {
+        let step = app.timer.delta_f32() * 60.;
+        self.mixer.update(app.timer.delta_f32());
+
+        for cooldown in &mut self.cue_cooldown {
+            *cooldown -= step;
+        }
+
+        for pending in &mut self.pending {
+            pending.2 -= step;
+        }
+
+        let mut i = 0;
+        while i < self.pending.len() {
+            if self.pending[i].2 <= 0. {
+                let (cue, pos, _) = self.pending.remove(i);
+                self.play_at(cue, pos, app);
+            } else {
+                i += 1;
+            }
+        }
+
+        for i in 0..self.sound.len() {
+            if let Some(s) = &self.sound[i] {
+                app.audio
+                    .set_volume(s, self.volume[i] * self.mixer.gain(self.bus[i]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1. / 60.;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn gain_multiplies_master_and_bus() {
+        let mixer = Mixer::new([0.5, 0.8, 1., 0.6]);
+        assert!(close(mixer.gain(Bus::Master), 0.5));
+        assert!(close(mixer.gain(Bus::Music), 0.4));
+        assert!(close(mixer.gain(Bus::Sfx), 0.5));
+        assert!(close(mixer.gain(Bus::Ambience), 0.3));
+    }
+
+    #[test]
+    fn duck_only_lowers_music_and_ambience() {
+        let mut mixer = Mixer::new([1.; 4]);
+        mixer.duck(120.);
+        for _ in 0..30 {
+            mixer.update(FRAME);
+        }
+        assert!(close(mixer.gain(Bus::Music), DUCK_LEVEL));
+        assert!(close(mixer.gain(Bus::Ambience), DUCK_LEVEL));
+        assert!(close(mixer.gain(Bus::Sfx), 1.));
+        assert!(close(mixer.gain(Bus::Master), 1.));
+    }
+
+    #[test]
+    fn duck_attacks_then_releases() {
+        let mut mixer = Mixer::new([1.; 4]);
+        mixer.duck(10.);
+
+        mixer.update(FRAME);
+        assert!(close(mixer.duck, DUCK_ATTACK));
+        for _ in 0..9 {
+            mixer.update(FRAME);
+        }
+        assert!(close(mixer.duck, 1.));
+
+        // The timer ran out, from here on it lets go slowly.
+        mixer.update(FRAME);
+        assert!(close(mixer.duck, 1. - DUCK_RELEASE));
+        for _ in 0..(1. / DUCK_RELEASE) as usize {
+            mixer.update(FRAME);
+        }
+        assert!(close(mixer.duck, 0.));
+        assert!(close(mixer.gain(Bus::Music), 1.));
+    }
+
+    #[test]
+    fn a_shorter_duck_does_not_cut_a_longer_one() {
+        let mut mixer = Mixer::new([1.; 4]);
+        mixer.duck(120.);
+        mixer.duck(5.);
+        assert!(close(mixer.duck_timer, 120.));
+    }
+
+    #[test]
+    fn fades_reach_their_target_and_stop() {
+        let mut mixer = Mixer::new([1.; 4]);
+        mixer.fade_to(0., 60.);
+        for _ in 0..30 {
+            mixer.update(FRAME);
+        }
+        assert!(close(mixer.gain(Bus::Music), 0.5));
+        for _ in 0..60 {
+            mixer.update(FRAME);
+        }
+        assert!(close(mixer.gain(Bus::Music), 0.));
+        assert!(close(mixer.gain(Bus::Sfx), 1.));
+    }
+
+    #[test]
+    fn volumes_and_fades_are_clamped() {
+        let mut mixer = Mixer::new([2., -1., 0.5, 0.5]);
+        assert!(close(mixer.volume(Bus::Master), 1.));
+        assert!(close(mixer.volume(Bus::Music), 0.));
+
+        mixer.set_volume(Bus::Sfx, 1.5);
+        assert!(close(mixer.volume(Bus::Sfx), 1.));
+        mixer.set_volume(Bus::Sfx, -0.5);
+        assert!(close(mixer.volume(Bus::Sfx), 0.));
+
+        mixer.set_fade(3.);
+        assert!(close(mixer.fade, 1.));
+        mixer.fade_to(-2., 1.);
+        mixer.update(FRAME);
+        assert!(close(mixer.fade, 0.));
+    }
+}
@@ -0,0 +1,103 @@
+use std::f32::consts::FRAC_PI_2;
+
+use notan::math::Vec2;
+
+use crate::synth::{tone, wav};
+
+pub const HEARING_RANGE: f32 = 700.;
+pub const PAN_WIDTH: f32 = 320.;
+// Echo dots travel 6 px a frame, so the sound coming back takes just as long.
+pub const SOUND_SPEED: f32 = 6.;
+
+// The audio backend only takes a volume per sound, so every cue is synthesized
+// up front at a few fixed stereo positions and the closest one is picked.
+pub const PAN_STEPS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Cue {
+    Ping,
+    EchoReturn,
+    Beacon,
+    Growl,
+    Impact,
+    Alarm,
+}
+
+impl Cue {
+    pub const ALL: [Cue; 6] = [
+        Cue::Ping,
+        Cue::EchoReturn,
+        Cue::Beacon,
+        Cue::Growl,
+        Cue::Impact,
+        Cue::Alarm,
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Cue::Ping => "ping",
+            Cue::EchoReturn => "echo_return",
+            Cue::Beacon => "beacon",
+            Cue::Growl => "growl",
+            Cue::Impact => "impact",
+            Cue::Alarm => "alarm",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Cue> {
+        Cue::ALL.into_iter().find(|c| c.key() == key)
+    }
+
+    // Minimum frames between two plays, so 61 wall hits don't turn into 61 clicks.
+    pub fn spacing(&self) -> f32 {
+        match self {
+            Cue::Ping => 0.,
+            Cue::EchoReturn => 3.,
+            Cue::Beacon => 10.,
+            Cue::Growl => 120.,
+            Cue::Impact => 10.,
+            Cue::Alarm => 90.,
+        }
+    }
+
+    pub fn synth(&self, pan: f32) -> Vec<u8> {
+        let samples = match self {
+            Cue::Ping => tone(1400., 900., 0.35, 9., 0.),
+            Cue::EchoReturn => tone(900., 700., 0.2, 18., 0.15),
+            Cue::Beacon => tone(600., 600., 0.25, 14., 0.),
+            Cue::Growl => tone(55., 40., 0.9, 3., 0.5),
+            Cue::Impact => tone(140., 50., 0.4, 8., 0.8),
+            Cue::Alarm => tone(880., 660., 0.4, 4., 0.),
+        };
+
+        let (left, right) = channel_gains(pan);
+        wav(&samples, left, right)
+    }
+}
+
+pub fn pan(listener: Vec2, source: Vec2) -> f32 {
+    ((source.x - listener.x) / PAN_WIDTH).clamp(-1., 1.)
+}
+
+pub fn attenuation(listener: Vec2, source: Vec2) -> f32 {
+    let falloff = (1. - listener.distance(source) / HEARING_RANGE).clamp(0., 1.);
+    falloff * falloff
+}
+
+pub fn return_delay(listener: Vec2, source: Vec2) -> f32 {
+    listener.distance(source) / SOUND_SPEED
+}
+
+pub fn pan_step(pan: f32) -> usize {
+    ((pan.clamp(-1., 1.) + 1.) * 0.5 * (PAN_STEPS - 1) as f32).round() as usize
+}
+
+pub fn step_pan(step: usize) -> f32 {
+    step as f32 / (PAN_STEPS - 1) as f32 * 2. - 1.
+}
+
+// Constant power pan law, -1 is hard left and 1 is hard right.
+pub fn channel_gains(pan: f32) -> (f32, f32) {
+    let angle = (pan.clamp(-1., 1.) + 1.) * 0.5 * FRAC_PI_2;
+    (angle.cos(), angle.sin())
+}
@@ -0,0 +1,80 @@
+use std::f32::consts::TAU;
+
+pub const SAMPLE_RATE: u32 = 22050;
+
+pub fn tone(start_freq: f32, end_freq: f32, seconds: f32, decay: f32, noise: f32) -> Vec<f32> {
+    let len = (seconds * SAMPLE_RATE as f32) as usize;
+    let mut phase = 0.;
+
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / len as f32;
+            let freq = start_freq + (end_freq - start_freq) * t;
+            phase += freq / SAMPLE_RATE as f32;
+
+            let envelope = (-decay * t).exp() * (t * 200.).min(1.);
+            let noise = (fastrand::f32() * 2. - 1.) * noise;
+            ((phase * TAU).sin() * (1. - noise.abs()) + noise) * envelope * 0.6
+        })
+        .collect()
+}
+
+pub fn wav(samples: &[f32], left: f32, right: f32) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 4;
+
+    let mut bytes = Vec::with_capacity(44 + data_len as usize);
+    bytes.extend_from_slice(b"RIFF");
+    bytes.extend_from_slice(&(36 + data_len).to_le_bytes());
+    bytes.extend_from_slice(b"WAVEfmt ");
+    bytes.extend_from_slice(&16u32.to_le_bytes());
+    bytes.extend_from_slice(&1u16.to_le_bytes());
+    bytes.extend_from_slice(&2u16.to_le_bytes());
+    bytes.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    bytes.extend_from_slice(&(SAMPLE_RATE * 4).to_le_bytes());
+    bytes.extend_from_slice(&4u16.to_le_bytes());
+    bytes.extend_from_slice(&16u16.to_le_bytes());
+    bytes.extend_from_slice(b"data");
+    bytes.extend_from_slice(&data_len.to_le_bytes());
+
+    for s in samples {
+        for gain in [left, right] {
+            let value = (s * gain).clamp(-1., 1.) * i16::MAX as f32;
+            bytes.extend_from_slice(&(value as i16).to_le_bytes());
+        }
+    }
+
+    bytes
+}
+
+// Loops hold a whole number of cycles of every component so they repeat without a click.
+pub fn drone(freq: f32, seconds: f32) -> Vec<f32> {
+    let len = (seconds * SAMPLE_RATE as f32) as usize;
+
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let wobble = 0.7 + 0.3 * (t / seconds * 2. * TAU).sin();
+            ((t * freq * TAU).sin() + 0.5 * (t * freq * 1.5 * TAU).sin()) * wobble * 0.35
+        })
+        .collect()
+}
+
+pub fn heartbeat(bpm: f32) -> Vec<f32> {
+    let period = 60. / bpm;
+    let len = (period * SAMPLE_RATE as f32) as usize;
+
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            [0., period * 0.28]
+                .iter()
+                .filter(|start| t >= **start)
+                .map(|start| {
+                    let local = t - start;
+                    (local * 48. * TAU).sin() * (-local * 25.).exp()
+                })
+                .sum::<f32>()
+                * 0.8
+        })
+        .collect()
+}
@@ -0,0 +1,202 @@
+// Turns what is happening around the player into a single 0..1 danger score,
+// which then drives the music layers and how often stingers play.
+
+const MONSTER_RANGE: f32 = 900.;
+// Frames without sending a ping before being blind counts fully.
+const BLIND_TIME: f32 = 600.;
+
+const RISE_SPEED: f32 = 0.01;
+const FALL_SPEED: f32 = 0.002;
+
+const CALM_STINGER_INTERVAL: f32 = 50. * 60.;
+const TENSE_STINGER_INTERVAL: f32 = 12. * 60.;
+
+// Intensity ranges over which each music layer fades in.
+const LAYERS: [(f32, f32); 2] = [(0.2, 0.55), (0.55, 0.9)];
+
+pub struct TensionInput {
+    pub monster_distance: f32,
+    pub monster_awake: bool,
+    pub friend_found: bool,
+    // 0 when the surroundings are full of fresh echo returns, 1 when nothing is lit.
+    pub darkness: f32,
+}
+
+pub fn danger(input: &TensionInput, since_reveal: f32) -> f32 {
+    if input.monster_awake {
+        return 1.;
+    }
+
+    let proximity = (1. - input.monster_distance / MONSTER_RANGE).clamp(0., 1.);
+    let friend = if input.friend_found { 0.2 } else { 0. };
+    let blind = (since_reveal / BLIND_TIME).clamp(0., 1.);
+
+    (proximity * 0.5 + friend + input.darkness.clamp(0., 1.) * 0.15 + blind * 0.15).clamp(0., 1.)
+}
+
+pub fn stinger_interval(intensity: f32) -> f32 {
+    CALM_STINGER_INTERVAL + (TENSE_STINGER_INTERVAL - CALM_STINGER_INTERVAL) * intensity
+}
+
+pub fn layer_volume(intensity: f32, layer: usize) -> f32 {
+    let (begin, end) = LAYERS[layer];
+    let t = ((intensity - begin) / (end - begin)).clamp(0., 1.);
+    t * t * (3. - 2. * t)
+}
+
+pub struct TensionDirector {
+    pub intensity: f32,
+    since_reveal: f32,
+    stinger_timer: f32,
+}
+
+impl TensionDirector {
+    pub fn new() -> Self {
+        Self {
+            intensity: 0.,
+            since_reveal: 0.,
+            stinger_timer: CALM_STINGER_INTERVAL,
+        }
+    }
+
+    pub fn revealed(&mut self) {
+        self.since_reveal = 0.;
+    }
+
+    // Returns true when a stinger should play this frame.
+    pub fn update(&mut self, input: &TensionInput, dt: f32) -> bool {
+        let step = dt * 60.;
+        self.since_reveal += step;
+
+        let target = danger(input, self.since_reveal);
+        if self.intensity < target {
+            self.intensity = (self.intensity + RISE_SPEED * step).min(target);
+        } else {
+            self.intensity = (self.intensity - FALL_SPEED * step).max(target);
+        }
+
+        // A rising score pulls the next stinger closer instead of waiting out a calm interval.
+        self.stinger_timer = self.stinger_timer.min(stinger_interval(self.intensity)) - step;
+        if self.stinger_timer <= 0. {
+            self.stinger_timer = stinger_interval(self.intensity);
+            return true;
+        }
+
+        false
+    }
+
+    pub fn layer_volume(&self, layer: usize) -> f32 {
+        layer_volume(self.intensity, layer)
+    }
+}
+
+impl Default for TensionDirector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRAME: f32 = 1. / 60.;
+
+    fn input(monster_distance: f32, monster_awake: bool) -> TensionInput {
+        TensionInput {
+            monster_distance,
+            monster_awake,
+            friend_found: false,
+            darkness: 0.,
+        }
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn danger_falls_off_with_distance() {
+        assert!(close(danger(&input(0., false), 0.), 0.5));
+        assert!(close(danger(&input(MONSTER_RANGE / 2., false), 0.), 0.25));
+        assert!(close(danger(&input(MONSTER_RANGE, false), 0.), 0.));
+        assert!(close(danger(&input(MONSTER_RANGE * 3., false), 0.), 0.));
+    }
+
+    #[test]
+    fn an_awake_creature_is_full_danger_anywhere() {
+        assert!(close(danger(&input(MONSTER_RANGE * 3., true), 0.), 1.));
+        assert!(danger(&input(10., false), 0.) < danger(&input(10., true), 0.));
+    }
+
+    #[test]
+    fn darkness_blindness_and_the_friend_add_up_and_clamp() {
+        let calm = TensionInput {
+            monster_distance: MONSTER_RANGE,
+            monster_awake: false,
+            friend_found: true,
+            darkness: 1.,
+        };
+        assert!(close(danger(&calm, 0.), 0.35));
+        assert!(close(danger(&calm, BLIND_TIME * 10.), 0.5));
+
+        let worst = TensionInput {
+            monster_distance: 0.,
+            darkness: 5.,
+            ..calm
+        };
+        assert!(close(danger(&worst, BLIND_TIME), 1.));
+    }
+
+    #[test]
+    fn intensity_rises_fast_and_falls_slowly() {
+        let mut director = TensionDirector::new();
+        director.update(&input(0., true), FRAME);
+        assert!(close(director.intensity, RISE_SPEED));
+
+        for _ in 0..200 {
+            director.update(&input(0., true), FRAME);
+        }
+        assert!(close(director.intensity, 1.));
+
+        // Once the creature is gone the music lingers instead of dropping at once.
+        director.update(&input(MONSTER_RANGE, false), FRAME);
+        assert!(close(director.intensity, 1. - FALL_SPEED));
+        for _ in 0..100 {
+            director.revealed();
+            director.update(&input(MONSTER_RANGE, false), FRAME);
+        }
+        assert!(director.intensity > 0.7);
+    }
+
+    #[test]
+    fn intensity_settles_on_the_target_without_overshooting() {
+        let mut director = TensionDirector::new();
+        for _ in 0..600 {
+            director.revealed();
+            director.update(&input(MONSTER_RANGE / 2., false), FRAME);
+        }
+        // One frame passes between the reveal and the update.
+        let target = danger(&input(MONSTER_RANGE / 2., false), 1.);
+        assert!(close(director.intensity, target));
+    }
+
+    #[test]
+    fn layers_fade_in_over_their_range() {
+        assert!(close(layer_volume(0., 0), 0.));
+        assert!(close(layer_volume(0.375, 0), 0.5));
+        assert!(close(layer_volume(0.55, 0), 1.));
+        assert!(close(layer_volume(0.55, 1), 0.));
+        assert!(close(layer_volume(1., 1), 1.));
+    }
+
+    #[test]
+    fn stingers_come_sooner_as_tension_rises() {
+        let mut director = TensionDirector::new();
+        let mut frames = 0;
+        while !director.update(&input(0., true), FRAME) {
+            frames += 1;
+        }
+        assert!((frames as f32) < CALM_STINGER_INTERVAL / 2.);
+    }
+}
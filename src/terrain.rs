@@ -0,0 +1,224 @@
+use notan::{app::Color, math::Vec2};
+
+use crate::material::Material;
+
+// A rectangle of cave pixels, `x1` and `y1` excluded.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Region {
+    pub x0: usize,
+    pub y0: usize,
+    pub x1: usize,
+    pub y1: usize,
+}
+
+impl Region {
+    pub fn is_empty(&self) -> bool {
+        self.x0 >= self.x1 || self.y0 >= self.y1
+    }
+
+    pub fn union(&self, other: &Region) -> Region {
+        Region {
+            x0: self.x0.min(other.x0),
+            y0: self.y0.min(other.y0),
+            x1: self.x1.max(other.x1),
+            y1: self.y1.max(other.y1),
+        }
+    }
+
+    pub fn overlaps(&self, other: &Region) -> bool {
+        self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+    }
+}
+
+// The cave's collision map, one RGBA pixel per world pixel, colored by material.
+// Every edit marks the pixels it touched as dirty until `take_dirty` hands them out, so
+// whatever keeps a copy of the cave (the minimap, distance fields) can catch up.
+pub struct Terrain {
+    pub width: usize,
+    pub height: usize,
+    bytes: Vec<u8>,
+    // What `reset` goes back to.
+    original: Vec<u8>,
+    dirty: Vec<Region>,
+}
+
+impl Terrain {
+    pub fn new(bytes: Vec<u8>, width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            original: bytes.clone(),
+            bytes,
+            dirty: vec![],
+        }
+    }
+
+    // Outside the cave reads as transparent, which counts as rock.
+    pub fn color(&self, x: f32, y: f32) -> Color {
+        let Some(index) = self.index(x, y) else {
+            return Color::new(0.0, 0.0, 0.0, 0.0);
+        };
+
+        let [r, g, b, a] = [0, 1, 2, 3].map(|i| self.bytes[index + i]);
+        Color::from_bytes(r, g, b, a)
+    }
+
+    pub fn is_water(&self, x: usize, y: usize) -> bool {
+        let index = (y * self.width + x) * 4;
+        x < self.width && self.bytes.get(index..index + 3) == Some(&[0, 0, 0])
+    }
+
+    pub fn material(&self, x: f32, y: f32) -> Material {
+        Material::from_color(self.color(x, y))
+    }
+
+    pub fn set_material(&mut self, x: usize, y: usize, material: Material) {
+        if x >= self.width || y >= self.height {
+            return;
+        }
+
+        self.write(x, y, material);
+        self.mark(Region {
+            x0: x,
+            y0: y,
+            x1: x + 1,
+            y1: y + 1,
+        });
+    }
+
+    // Turns whatever `can_carve` allows within `radius` of `center` into water.
+    // Returns how many pixels were carved.
+    pub fn carve_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        can_carve: impl Fn(Material) -> bool,
+    ) -> usize {
+        self.paint_circle(center, radius, Material::Water, |m, _| {
+            m != Material::Water && can_carve(m)
+        })
+    }
+
+    // Fills the water within `radius` of `center`, leaving room for the sub at `spare`
+    // so it doesn't end up stuck inside.
+    pub fn fill_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        material: Material,
+        spare: Vec2,
+    ) -> usize {
+        self.paint_circle(center, radius, material, |m, pos| {
+            m == Material::Water && pos.distance(spare) >= 24.
+        })
+    }
+
+    // Fills the water in `region`, returns how many pixels were filled.
+    pub fn fill_region(&mut self, region: Region, material: Material) -> usize {
+        let region = self.clip(region);
+        let mut filled = 0;
+        for y in region.y0..region.y1 {
+            for x in region.x0..region.x1 {
+                if self.material(x as f32, y as f32) == Material::Water {
+                    self.write(x, y, material);
+                    filled += 1;
+                }
+            }
+        }
+
+        if filled > 0 {
+            self.mark(region);
+        }
+        filled
+    }
+
+    // Makes the cave as it is now the one `reset` goes back to.
+    pub fn bake(&mut self) {
+        self.original.clone_from(&self.bytes);
+        self.dirty.clear();
+    }
+
+    // Undoes every edit since the last `bake`.
+    pub fn reset(&mut self) {
+        self.bytes.clone_from(&self.original);
+        self.mark(Region {
+            x0: 0,
+            y0: 0,
+            x1: self.width,
+            y1: self.height,
+        });
+    }
+
+    // Regions edited since the last call, merged where they overlap.
+    pub fn take_dirty(&mut self) -> Vec<Region> {
+        let mut merged: Vec<Region> = vec![];
+        for region in std::mem::take(&mut self.dirty) {
+            match merged.iter_mut().find(|m| m.overlaps(&region)) {
+                Some(m) => *m = m.union(&region),
+                None => merged.push(region),
+            }
+        }
+        merged
+    }
+
+    fn paint_circle(
+        &mut self,
+        center: Vec2,
+        radius: f32,
+        material: Material,
+        replace: impl Fn(Material, Vec2) -> bool,
+    ) -> usize {
+        let region = self.clip(Region {
+            x0: (center.x - radius).max(0.) as usize,
+            y0: (center.y - radius).max(0.) as usize,
+            x1: (center.x + radius).max(0.) as usize + 1,
+            y1: (center.y + radius).max(0.) as usize + 1,
+        });
+
+        let mut painted = 0;
+        for y in region.y0..region.y1 {
+            for x in region.x0..region.x1 {
+                let pos = Vec2::new(x as f32, y as f32);
+                if pos.distance(center) <= radius && replace(self.material(pos.x, pos.y), pos) {
+                    self.write(x, y, material);
+                    painted += 1;
+                }
+            }
+        }
+
+        if painted > 0 {
+            self.mark(region);
+        }
+        painted
+    }
+
+    fn write(&mut self, x: usize, y: usize, material: Material) {
+        let [r, g, b] = material.color();
+        let index = (y * self.width + x) * 4;
+        self.bytes[index..index + 4].copy_from_slice(&[r, g, b, 255]);
+    }
+
+    fn mark(&mut self, region: Region) {
+        if !region.is_empty() {
+            self.dirty.push(region);
+        }
+    }
+
+    fn clip(&self, region: Region) -> Region {
+        Region {
+            x0: region.x0.min(self.width),
+            y0: region.y0.min(self.height),
+            x1: region.x1.min(self.width),
+            y1: region.y1.min(self.height),
+        }
+    }
+
+    fn index(&self, x: f32, y: f32) -> Option<usize> {
+        if x < 0. || y < 0. {
+            return None;
+        }
+
+        let (x, y) = (x.floor() as usize, y.floor() as usize);
+        (x < self.width && y < self.height).then(|| (y * self.width + x) * 4)
+    }
+}
@@ -0,0 +1,230 @@
+use notan::math::Vec2;
+
+use crate::{
+    Scene,
+    level::parse_numbers,
+    message::{Message, Priority},
+    spatial::Cue,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    Anywhere,
+    Circle(Vec2, f32),
+    Rect(Vec2, Vec2),
+    Polygon(Vec<Vec2>),
+}
+
+impl Shape {
+    pub fn contains(&self, p: Vec2) -> bool {
+        match self {
+            Shape::Anywhere => true,
+            Shape::Circle(pos, radius) => p.distance(*pos) < *radius,
+            Shape::Rect(min, max) => p.x > min.x && p.x < max.x && p.y > min.y && p.y < max.y,
+            Shape::Polygon(points) => {
+                // Even-odd rule: count the edges a ray going right from `p` crosses.
+                let mut inside = false;
+                let mut j = points.len() - 1;
+                for i in 0..points.len() {
+                    let (a, b) = (points[i], points[j]);
+                    if (a.y > p.y) != (b.y > p.y)
+                        && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x
+                    {
+                        inside = !inside;
+                    }
+                    j = i;
+                }
+                inside
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    FriendFound(bool),
+    Item(String, bool),
+}
+
+#[derive(Clone, Debug)]
+pub enum TriggerAction {
+    Message(Message),
+    Cue(Cue),
+    ActivateMonster,
+    SpawnBeacon(Vec2, i32),
+    ChangeScene(Scene, String),
+    Give(String),
+}
+
+// What the conditions get to look at.
+pub struct TriggerContext<'a> {
+    pub player_pos: Vec2,
+    pub friend_found: bool,
+    pub items: &'a [String],
+}
+
+#[derive(Clone, Debug)]
+pub struct Trigger {
+    pub shape: Shape,
+    pub conditions: Vec<Condition>,
+    // Once triggers fire a single time, repeating ones every time the player enters.
+    pub repeat: bool,
+    pub action: TriggerAction,
+    pub fired: bool,
+    inside: bool,
+}
+
+impl Trigger {
+    pub fn new(
+        shape: Shape,
+        conditions: Vec<Condition>,
+        repeat: bool,
+        action: TriggerAction,
+    ) -> Self {
+        Self {
+            shape,
+            conditions,
+            repeat,
+            action,
+            fired: false,
+            inside: false,
+        }
+    }
+
+    fn conditions_met(&self, ctx: &TriggerContext) -> bool {
+        self.conditions.iter().all(|c| match c {
+            Condition::FriendFound(wanted) => ctx.friend_found == *wanted,
+            Condition::Item(name, wanted) => ctx.items.contains(name) == *wanted,
+        })
+    }
+
+    // Returns the action when the trigger fires this frame.
+    pub fn check(&mut self, ctx: &TriggerContext) -> Option<&TriggerAction> {
+        let was_inside = self.inside;
+        self.inside = self.shape.contains(ctx.player_pos) && self.conditions_met(ctx);
+
+        if !self.inside || was_inside || (self.fired && !self.repeat) {
+            return None;
+        }
+
+        self.fired = true;
+        Some(&self.action)
+    }
+}
+
+// trigger <shape> ; <conditions> ; <once|repeat> ; <action> [; <action fields>...]
+pub fn parse_trigger(fields: &[&str]) -> Result<Trigger, String> {
+    let [shape, conditions, mode, action @ ..] = fields else {
+        return Err("a trigger needs at least 4 fields".to_string());
+    };
+
+    let repeat = match *mode {
+        "once" => false,
+        "repeat" => true,
+        _ => return Err(format!("bad trigger mode `{mode}`")),
+    };
+
+    Ok(Trigger::new(
+        parse_shape(shape)?,
+        parse_conditions(conditions)?,
+        repeat,
+        parse_action(action)?,
+    ))
+}
+
+pub fn parse_shape(text: &str) -> Result<Shape, String> {
+    let (kind, args) = text.split_once(' ').unwrap_or((text, ""));
+    let numbers = parse_numbers(args)?;
+
+    match (kind, numbers.as_slice()) {
+        ("anywhere", []) => Ok(Shape::Anywhere),
+        ("circle", [x, y, radius]) => Ok(Shape::Circle(Vec2::new(*x, *y), *radius)),
+        ("rect", [x0, y0, x1, y1]) => Ok(Shape::Rect(
+            Vec2::new(x0.min(*x1), y0.min(*y1)),
+            Vec2::new(x0.max(*x1), y0.max(*y1)),
+        )),
+        ("polygon", points) if points.len() >= 6 && points.len() % 2 == 0 => Ok(Shape::Polygon(
+            points.chunks(2).map(|p| Vec2::new(p[0], p[1])).collect(),
+        )),
+        _ => Err(format!("bad shape `{text}`")),
+    }
+}
+
+fn parse_conditions(text: &str) -> Result<Vec<Condition>, String> {
+    if text == "-" {
+        return Ok(vec![]);
+    }
+
+    text.split_whitespace()
+        .map(|word| {
+            let (wanted, name) = match word.strip_prefix('!') {
+                Some(name) => (false, name),
+                None => (true, word),
+            };
+            match name.split_once(':') {
+                None if name == "friend_found" => Ok(Condition::FriendFound(wanted)),
+                Some(("item", item)) if !item.is_empty() => {
+                    Ok(Condition::Item(item.to_string(), wanted))
+                }
+                _ => Err(format!("bad condition `{word}`")),
+            }
+        })
+        .collect()
+}
+
+fn parse_action(fields: &[&str]) -> Result<TriggerAction, String> {
+    match fields {
+        ["message", message @ ..] => parse_message(message).map(TriggerAction::Message),
+        ["cue", name] => Cue::from_key(name)
+            .map(TriggerAction::Cue)
+            .ok_or(format!("unknown cue `{name}`")),
+        ["activate_monster"] => Ok(TriggerAction::ActivateMonster),
+        ["spawn", entity] => {
+            let (kind, args) = entity.split_once(' ').unwrap_or((entity, ""));
+            match (kind, parse_numbers(args)?.as_slice()) {
+                ("beacon", [x, y]) => Ok(TriggerAction::SpawnBeacon(Vec2::new(*x, *y), 60)),
+                ("beacon", [x, y, freq]) => {
+                    Ok(TriggerAction::SpawnBeacon(Vec2::new(*x, *y), *freq as i32))
+                }
+                _ => Err(format!("bad entity `{entity}`")),
+            }
+        }
+        ["scene", scene, text @ ..] => {
+            let scene = match *scene {
+                "start" => Scene::Start,
+                "game" => Scene::Game,
+                "end" => Scene::End,
+                _ => return Err(format!("bad scene `{scene}`")),
+            };
+            Ok(TriggerAction::ChangeScene(scene, text.join("; ")))
+        }
+        ["give", item] if !item.is_empty() => Ok(TriggerAction::Give(item.to_string())),
+        _ => Err(format!("bad action `{}`", fields.join(" ; "))),
+    }
+}
+
+// <priority> ; <frames> ; <speaker> ; <text>
+pub fn parse_message(fields: &[&str]) -> Result<Message, String> {
+    let [priority, duration, speaker, text] = fields else {
+        return Err("a message needs priority, frames, speaker and text".to_string());
+    };
+
+    let priority = match *priority {
+        "low" => Priority::Low,
+        "normal" => Priority::Normal,
+        "high" => Priority::High,
+        _ => return Err(format!("bad priority `{priority}`")),
+    };
+
+    let duration = duration
+        .parse::<f32>()
+        .map_err(|_| format!("bad duration `{duration}`"))?;
+
+    let speaker = if *speaker == "-" {
+        None
+    } else {
+        Some(*speaker)
+    };
+
+    Ok(Message::new(text, speaker, priority, duration))
+}
@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+use notan::log;
+
+use crate::config;
+
+const TUNING_FILE: &str = "tuning.cfg";
+
+// Gameplay numbers. The built-in values come from assets/tuning.cfg and a file
+// with the same name in the config directory can override any of them.
+pub struct Tuning {
+    defaults: HashMap<String, String>,
+    overrides: HashMap<String, String>,
+}
+
+impl Tuning {
+    pub fn load() -> Self {
+        Self {
+            defaults: config::parse(include_str!("assets/tuning.cfg")),
+            overrides: config::load(TUNING_FILE),
+        }
+    }
+
+    pub fn get(&self, key: &str) -> f32 {
+        if let Some(value) = self.overrides.get(key) {
+            match value.parse::<f32>() {
+                Ok(v) if v.is_finite() => return v,
+                _ => log::warn!("Invalid tuning `{key} = {value}`, using the default"),
+            }
+        }
+
+        self.defaults
+            .get(key)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or_else(|| panic!("missing default tuning for `{key}`"))
+    }
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        Self::load()
+    }
+}
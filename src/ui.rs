@@ -0,0 +1,287 @@
+use notan::{
+    app::{App, Color},
+    draw::{Draw, DrawShapes, DrawTextSection, Font},
+    math::{Rect, Vec2},
+    prelude::KeyCode,
+};
+
+use crate::keys;
+
+const HIGHLIGHT: Color = Color::new(1., 1., 1., 0.25);
+const ACCENT: Color = Color::new(0.8, 0.0, 0.8, 1.0);
+
+pub enum WidgetKind {
+    Button,
+    Slider(f32),
+    Toggle(bool),
+    List(Vec<String>, usize),
+    // A button that shows its current value, used for key bindings.
+    Binding(String),
+}
+
+pub struct Widget {
+    pub id: &'static str,
+    pub label: String,
+    pub kind: WidgetKind,
+    rect: Rect,
+}
+
+impl Widget {
+    pub fn button(id: &'static str, label: &str) -> Self {
+        Self::new(id, label, WidgetKind::Button)
+    }
+
+    pub fn slider(id: &'static str, label: &str, value: f32) -> Self {
+        Self::new(id, label, WidgetKind::Slider(value.clamp(0., 1.)))
+    }
+
+    pub fn toggle(id: &'static str, label: &str, on: bool) -> Self {
+        Self::new(id, label, WidgetKind::Toggle(on))
+    }
+
+    pub fn binding(id: &'static str, label: &str, value: &str) -> Self {
+        Self::new(id, label, WidgetKind::Binding(value.to_string()))
+    }
+
+    pub fn list(id: &'static str, label: &str, options: Vec<String>, selected: usize) -> Self {
+        let selected = selected.min(options.len().saturating_sub(1));
+        Self::new(id, label, WidgetKind::List(options, selected))
+    }
+
+    fn new(id: &'static str, label: &str, kind: WidgetKind) -> Self {
+        Self {
+            id,
+            label: label.to_string(),
+            kind,
+            rect: Rect::default(),
+        }
+    }
+
+    // Where a slider's bar sits inside the widget, used for both drawing and clicking.
+    fn bar(&self) -> Rect {
+        Rect {
+            x: self.rect.x + self.rect.width * 0.55,
+            y: self.rect.y + self.rect.height * 0.4,
+            width: self.rect.width * 0.4,
+            height: self.rect.height * 0.2,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum UiEvent {
+    Pressed(&'static str),
+    Slider(&'static str, f32),
+    Toggle(&'static str, bool),
+    List(&'static str, usize),
+    Back,
+}
+
+// One frame of menu input. Kept separate from the devices so anything that can
+// move focus and confirm (keyboard, mouse, a pad) can drive the same menus.
+#[derive(Default)]
+pub struct UiInput {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub confirm: bool,
+    pub back: bool,
+    pub pointer: Option<Vec2>,
+    pub click: bool,
+    pub drag: bool,
+    pub key: Option<KeyCode>,
+}
+
+impl UiInput {
+    pub fn read(app: &App) -> Self {
+        let kb = &app.keyboard;
+        let pointer = Vec2::new(app.mouse.x, app.mouse.y);
+        let pointing =
+            app.mouse.is_moving() || app.mouse.left_was_pressed() || app.mouse.left_is_down();
+
+        Self {
+            up: kb.was_pressed(KeyCode::Up) || kb.was_pressed(KeyCode::W),
+            down: kb.was_pressed(KeyCode::Down) || kb.was_pressed(KeyCode::S),
+            left: kb.was_pressed(KeyCode::Left) || kb.was_pressed(KeyCode::A),
+            right: kb.was_pressed(KeyCode::Right) || kb.was_pressed(KeyCode::D),
+            confirm: kb.was_pressed(KeyCode::Return) || kb.was_pressed(KeyCode::Space),
+            back: kb.was_pressed(KeyCode::Escape),
+            pointer: pointing.then_some(pointer),
+            click: app.mouse.left_was_pressed(),
+            drag: app.mouse.left_is_down(),
+            key: keys::all().find(|k| kb.was_pressed(*k)),
+        }
+    }
+}
+
+pub struct Menu {
+    pub widgets: Vec<Widget>,
+    focus: usize,
+}
+
+impl Menu {
+    pub fn new(widgets: Vec<Widget>) -> Self {
+        Self { widgets, focus: 0 }
+    }
+
+    pub fn get(&mut self, id: &str) -> Option<&mut Widget> {
+        self.widgets.iter_mut().find(|w| w.id == id)
+    }
+
+    // Stacks the widgets in a column starting at `pos`.
+    pub fn layout(&mut self, pos: Vec2, size: Vec2, spacing: f32) {
+        for (i, widget) in self.widgets.iter_mut().enumerate() {
+            widget.rect = Rect {
+                x: pos.x,
+                y: pos.y + i as f32 * (size.y + spacing),
+                width: size.x,
+                height: size.y,
+            };
+        }
+    }
+
+    pub fn update(&mut self, input: &UiInput) -> Option<UiEvent> {
+        if self.widgets.is_empty() {
+            return None;
+        }
+
+        if input.back {
+            return Some(UiEvent::Back);
+        }
+
+        if input.up {
+            self.focus = (self.focus + self.widgets.len() - 1) % self.widgets.len();
+        }
+        if input.down {
+            self.focus = (self.focus + 1) % self.widgets.len();
+        }
+
+        if let Some(pointer) = input.pointer
+            && let Some(i) = self.widgets.iter().position(|w| contains(&w.rect, pointer))
+        {
+            self.focus = i;
+            if input.click || input.drag {
+                return self.pointer_event(pointer, input.click);
+            }
+        }
+
+        let step = input.right as i32 - input.left as i32;
+        let widget = &mut self.widgets[self.focus];
+        let id = widget.id;
+
+        match &mut widget.kind {
+            WidgetKind::Button | WidgetKind::Binding(_) if input.confirm => {
+                Some(UiEvent::Pressed(id))
+            }
+            WidgetKind::Slider(value) if step != 0 => {
+                *value = (*value + step as f32 * 0.1).clamp(0., 1.);
+                Some(UiEvent::Slider(id, *value))
+            }
+            WidgetKind::Toggle(on) if input.confirm || step != 0 => {
+                *on = !*on;
+                Some(UiEvent::Toggle(id, *on))
+            }
+            WidgetKind::List(options, selected)
+                if (input.confirm || step != 0) && !options.is_empty() =>
+            {
+                let step = if step == 0 { 1 } else { step };
+                *selected = (*selected as i32 + step).rem_euclid(options.len() as i32) as usize;
+                Some(UiEvent::List(id, *selected))
+            }
+            _ => None,
+        }
+    }
+
+    fn pointer_event(&mut self, pointer: Vec2, click: bool) -> Option<UiEvent> {
+        let widget = &mut self.widgets[self.focus];
+        let bar = widget.bar();
+        let id = widget.id;
+
+        match &mut widget.kind {
+            WidgetKind::Button | WidgetKind::Binding(_) if click => Some(UiEvent::Pressed(id)),
+            WidgetKind::Slider(value) if pointer.x >= bar.x - 10. => {
+                *value = ((pointer.x - bar.x) / bar.width).clamp(0., 1.);
+                Some(UiEvent::Slider(id, *value))
+            }
+            WidgetKind::Toggle(on) if click => {
+                *on = !*on;
+                Some(UiEvent::Toggle(id, *on))
+            }
+            WidgetKind::List(options, selected) if click && !options.is_empty() => {
+                *selected = (*selected + 1) % options.len();
+                Some(UiEvent::List(id, *selected))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn render(&self, font: &Font, draw: &mut Draw) {
+        for (i, widget) in self.widgets.iter().enumerate() {
+            let r = &widget.rect;
+
+            if i == self.focus {
+                draw.rect((r.x, r.y), (r.width, r.height)).color(HIGHLIGHT);
+            }
+
+            match &widget.kind {
+                WidgetKind::Button => {
+                    draw.text(font, &widget.label)
+                        .size(r.height * 0.66)
+                        .color(Color::WHITE)
+                        .h_align_center()
+                        .v_align_middle()
+                        .position(r.x + r.width / 2., r.y + r.height / 2.);
+                }
+                WidgetKind::Slider(value) => {
+                    draw_label(font, widget, draw);
+
+                    let bar = widget.bar();
+                    draw.rect((bar.x, bar.y), (bar.width, bar.height))
+                        .stroke_color(Color::WHITE)
+                        .stroke(2.);
+                    draw.rect((bar.x, bar.y), (bar.width * value, bar.height))
+                        .color(ACCENT);
+                }
+                WidgetKind::Toggle(on) => {
+                    draw_label(font, widget, draw);
+                    draw_value(font, widget, if *on { "ON" } else { "OFF" }, draw);
+                }
+                WidgetKind::List(options, selected) => {
+                    draw_label(font, widget, draw);
+                    if let Some(option) = options.get(*selected) {
+                        draw_value(font, widget, &format!("< {option} >"), draw);
+                    }
+                }
+                WidgetKind::Binding(value) => {
+                    draw_label(font, widget, draw);
+                    draw_value(font, widget, value, draw);
+                }
+            }
+        }
+    }
+}
+
+fn draw_label(font: &Font, widget: &Widget, draw: &mut Draw) {
+    let r = &widget.rect;
+    draw.text(font, &widget.label)
+        .size(r.height * 0.5)
+        .color(Color::WHITE)
+        .h_align_left()
+        .v_align_middle()
+        .position(r.x + 12., r.y + r.height / 2.);
+}
+
+fn draw_value(font: &Font, widget: &Widget, value: &str, draw: &mut Draw) {
+    let r = &widget.rect;
+    draw.text(font, value)
+        .size(r.height * 0.5)
+        .color(ACCENT)
+        .h_align_right()
+        .v_align_middle()
+        .position(r.x + r.width - 12., r.y + r.height / 2.);
+}
+
+fn contains(rect: &Rect, p: Vec2) -> bool {
+    p.x > rect.x && p.x < rect.x + rect.width && p.y > rect.y && p.y < rect.y + rect.height
+}
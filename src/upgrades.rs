@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+
+use notan::log;
+
+use crate::config;
+
+const PROFILE_FILE: &str = "profile.cfg";
+
+pub const RESCUE_REWARD: u32 = 100;
+pub const MAX_LEVEL: usize = 3;
+const COSTS: [u32; MAX_LEVEL] = [60, 120, 240];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Upgrade {
+    Engine,
+    Rudder,
+    Sonar,
+    Array,
+    Beacons,
+}
+
+pub const UPGRADES: usize = 5;
+
+impl Upgrade {
+    pub const ALL: [Upgrade; UPGRADES] = [
+        Upgrade::Engine,
+        Upgrade::Rudder,
+        Upgrade::Sonar,
+        Upgrade::Array,
+        Upgrade::Beacons,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            Upgrade::Engine => "engine",
+            Upgrade::Rudder => "rudder",
+            Upgrade::Sonar => "sonar",
+            Upgrade::Array => "array",
+            Upgrade::Beacons => "beacons",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Upgrade::Engine => "ENGINE",
+            Upgrade::Rudder => "RUDDER",
+            Upgrade::Sonar => "SONAR RECHARGE",
+            Upgrade::Array => "SONAR ARRAY",
+            Upgrade::Beacons => "BEACON RACK",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Upgrade> {
+        Upgrade::ALL.into_iter().find(|u| u.key() == key)
+    }
+
+    // Price of the level after `level`, or None once the track is maxed out.
+    pub fn cost(level: usize) -> Option<u32> {
+        COSTS.get(level).copied()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubStats {
+    pub max_speed: f32,
+    pub acceleration: f32,
+    // Degrees per second.
+    pub turn_rate: f32,
+    // Frames between pings.
+    pub echo_cooldown: f32,
+    pub echo_rays: usize,
+    pub beacons: i32,
+    pub torpedoes: i32,
+}
+
+impl SubStats {
+    pub fn new() -> Self {
+        Self {
+            max_speed: 3.,
+            acceleration: 1.,
+            turn_rate: 60.,
+            echo_cooldown: 40.,
+            echo_rays: 61,
+            beacons: 3,
+            torpedoes: 2,
+        }
+    }
+
+    pub fn with_upgrades(levels: &[usize; UPGRADES]) -> Self {
+        let mut stats = Self::new();
+        let level = |u: Upgrade| levels[u as usize].min(MAX_LEVEL) as f32;
+
+        stats.max_speed += level(Upgrade::Engine) * 0.4;
+        stats.acceleration += level(Upgrade::Engine) * 0.15;
+        stats.turn_rate += level(Upgrade::Rudder) * 12.;
+        stats.echo_cooldown -= level(Upgrade::Sonar) * 6.;
+        stats.echo_rays += level(Upgrade::Array) as usize * 20;
+        stats.beacons += level(Upgrade::Beacons) as i32;
+
+        stats
+    }
+}
+
+impl Default for SubStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Progress kept between runs: credits, the bought upgrade levels, the keys of the
+// story logs found so far and of the rewards already paid out.
+pub struct Profile {
+    pub credits: u32,
+    pub levels: [usize; UPGRADES],
+    pub logs: Vec<String>,
+    pub claimed: Vec<String>,
+}
+
+impl Profile {
+    pub fn load() -> Self {
+        Self::from_values(&config::load(PROFILE_FILE))
+    }
+
+    pub fn save(&self) {
+        config::save(PROFILE_FILE, &self.to_values());
+    }
+
+    pub fn from_values(values: &HashMap<String, String>) -> Self {
+        let read = |key: &str, max: u32| -> u32 {
+            let Some(value) = values.get(key) else {
+                return 0;
+            };
+            value.parse().ok().filter(|v| *v <= max).unwrap_or_else(|| {
+                log::warn!("Invalid profile entry `{key} = {value}`, using 0");
+                0
+            })
+        };
+
+        let keys = |key: &str| -> Vec<String> {
+            values
+                .get(key)
+                .map(|keys| keys.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default()
+        };
+
+        Self {
+            credits: read("credits", u32::MAX),
+            levels: Upgrade::ALL
+                .map(|u| read(&format!("upgrade.{}", u.key()), MAX_LEVEL as u32) as usize),
+            logs: keys("logs"),
+            claimed: keys("claimed"),
+        }
+    }
+
+    pub fn to_values(&self) -> Vec<(String, String)> {
+        let mut values = vec![("credits".to_string(), self.credits.to_string())];
+        for upgrade in Upgrade::ALL {
+            values.push((
+                format!("upgrade.{}", upgrade.key()),
+                self.levels[upgrade as usize].to_string(),
+            ));
+        }
+        values.push(("logs".to_string(), self.logs.join(" ")));
+        values.push(("claimed".to_string(), self.claimed.join(" ")));
+        values
+    }
+
+    // Pays a salvage or rescue reward once per key, so restoring a checkpoint or a save
+    // can't pay it again. Returns false when it was already claimed.
+    pub fn claim(&mut self, key: &str, credits: u32) -> bool {
+        if !self.record_claim(key, credits) {
+            return false;
+        }
+        self.save();
+        true
+    }
+
+    fn record_claim(&mut self, key: &str, credits: u32) -> bool {
+        if self.claimed.iter().any(|c| c == key) {
+            return false;
+        }
+        self.claimed.push(key.to_string());
+        self.credits = self.credits.saturating_add(credits);
+        true
+    }
+
+    pub fn buy(&mut self, upgrade: Upgrade) -> bool {
+        let level = self.levels[upgrade as usize];
+        match Upgrade::cost(level) {
+            Some(cost) if cost <= self.credits => {
+                self.credits -= cost;
+                self.levels[upgrade as usize] += 1;
+                self.save();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Returns false when the log was already in the journal.
+    pub fn collect_log(&mut self, key: &str) -> bool {
+        if self.logs.iter().any(|l| l == key) {
+            return false;
+        }
+        self.logs.push(key.to_string());
+        self.save();
+        true
+    }
+
+    pub fn stats(&self) -> SubStats {
+        SubStats::with_upgrades(&self.levels)
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Self {
+        Self::load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewards_are_paid_once_per_key() {
+        let mut profile = Profile::from_values(&HashMap::new());
+        assert!(profile.record_claim("salvage.0", 50));
+        assert!(profile.record_claim("survivor.0", 100));
+        assert_eq!(profile.credits, 150);
+
+        // Restoring a checkpoint or reloading the profile brings the rewards back.
+        let mut profile = Profile::from_values(&profile.to_values().into_iter().collect());
+        assert!(!profile.record_claim("salvage.0", 50));
+        assert!(!profile.record_claim("survivor.0", 100));
+        assert!(profile.record_claim("salvage.1", 25));
+        assert_eq!(profile.credits, 175);
+    }
+}